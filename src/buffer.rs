@@ -0,0 +1,341 @@
+// Geospatial Buffers
+use crate::coord;
+use crate::dist::{DistanceMethod, destination_point};
+use geo::{
+    BooleanOps, BoundingRect, Coord, Geometry, Line, LineString, MultiPolygon, Point, Polygon,
+};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+// Arc segments per full circle when not otherwise specified
+pub const DEFAULT_SEGMENTS: usize = 32;
+
+pub fn buffer_point(
+    point: &Point,
+    distance: f64,
+    segments: usize,
+    method: &DistanceMethod,
+) -> Polygon {
+    // Circle of radius distance (m) - geodesic on lon / lat, euclidean on projected data
+    if distance <= 0.0 {
+        return Polygon::new(LineString::new(vec![]), vec![]);
+    }
+    if !method.is_geographic() {
+        return circle(point.0, distance, segments);
+    }
+
+    // Bearings run clockwise, reverse for a counter-clockwise exterior
+    let segments = segments.max(4);
+    let mut coords: Vec<Coord> = (0..segments)
+        .map(|idx| {
+            let bearing = 360.0 * idx as f64 / segments as f64;
+            destination_point(point, bearing, distance, method).0
+        })
+        .collect();
+    coords.reverse();
+
+    return Polygon::new(LineString::from(coords), vec![]);
+}
+
+pub fn buffer_geometry(
+    geometry: &Geometry,
+    distance: f64,
+    segments: usize,
+    method: &DistanceMethod,
+) -> MultiPolygon {
+    // Buffer by distance (m), negative distances shrink polygons & empty everything else
+    if let Geometry::Point(point) = geometry {
+        return to_multipolygon(buffer_point(point, distance, segments, method));
+    }
+
+    let ellipsoid = match method.ellipsoid() {
+        Some(ellipsoid) => ellipsoid,
+        None => return planar_buffer(geometry, distance, segments),
+    };
+
+    // Buffer lon / lat data in a transverse mercator CRS centred on the geometry
+    let centre = match geometry.bounding_rect() {
+        Some(rect) => rect.center(),
+        None => return MultiPolygon::new(vec![]),
+    };
+    let (a, b) = (ellipsoid.semi_major_axis, ellipsoid.semi_minor_axis());
+    let geographic_proj = format!("+proj=longlat +a={} +b={} +no_defs", a, b);
+    let local_proj = format!(
+        "+proj=tmerc +lat_0={} +lon_0={} +k=1 +x_0=0 +y_0=0 +a={} +b={} +units=m +no_defs",
+        centre.y, centre.x, a, b
+    );
+
+    let local_geometry = coord::update_geometry_proj(geometry, &geographic_proj, &local_proj);
+    let local_buffer = planar_buffer(&local_geometry, distance, segments);
+    let buffer = coord::update_geometry_proj(
+        &Geometry::MultiPolygon(local_buffer),
+        &local_proj,
+        &geographic_proj,
+    );
+
+    match buffer {
+        Geometry::MultiPolygon(multipolygon) => return multipolygon,
+        _ => return MultiPolygon::new(vec![]),
+    }
+}
+
+pub fn buffer_polygon(
+    polygon: &Polygon,
+    distance: f64,
+    segments: usize,
+    method: &DistanceMethod,
+) -> MultiPolygon {
+    return buffer_geometry(
+        &Geometry::Polygon(polygon.clone()),
+        distance,
+        segments,
+        method,
+    );
+}
+
+pub fn buffer_linestring(
+    linestring: &LineString,
+    distance: f64,
+    segments: usize,
+    method: &DistanceMethod,
+) -> MultiPolygon {
+    return buffer_geometry(
+        &Geometry::LineString(linestring.clone()),
+        distance,
+        segments,
+        method,
+    );
+}
+
+fn planar_buffer(geometry: &Geometry, distance: f64, segments: usize) -> MultiPolygon {
+    // Buffer in the geometry's own (metric) units
+    match geometry {
+        Geometry::Polygon(polygon) => {
+            return buffer_rings(&to_multipolygon(polygon.clone()), distance, segments);
+        }
+        Geometry::MultiPolygon(multipolygon) => {
+            return buffer_rings(multipolygon, distance, segments);
+        }
+        Geometry::Rect(rect) => {
+            return buffer_rings(&to_multipolygon(rect.to_polygon()), distance, segments);
+        }
+        Geometry::Triangle(triangle) => {
+            return buffer_rings(&to_multipolygon(triangle.to_polygon()), distance, segments);
+        }
+        Geometry::GeometryCollection(collection) => {
+            return union_all(
+                collection
+                    .iter()
+                    .map(|geometry| planar_buffer(geometry, distance, segments))
+                    .collect(),
+            );
+        }
+        _ => (),
+    }
+
+    // Points & lines only have a positive buffer
+    if distance <= 0.0 {
+        return MultiPolygon::new(vec![]);
+    }
+
+    let parts: Vec<MultiPolygon> = match geometry {
+        Geometry::Point(point) => vec![to_multipolygon(circle(point.0, distance, segments))],
+        Geometry::MultiPoint(points) => points
+            .iter()
+            .map(|point| to_multipolygon(circle(point.0, distance, segments)))
+            .collect(),
+        Geometry::Line(line) => vec![to_multipolygon(capsule(line, distance, segments))],
+        Geometry::LineString(linestring) => linestring_capsules(linestring, distance, segments),
+        Geometry::MultiLineString(linestrings) => linestrings
+            .iter()
+            .flat_map(|linestring| linestring_capsules(linestring, distance, segments))
+            .collect(),
+        _ => vec![],
+    };
+
+    return union_all(parts);
+}
+
+fn buffer_rings(multipolygon: &MultiPolygon, distance: f64, segments: usize) -> MultiPolygon {
+    // Grow or shrink polygons by the capsules swept along every ring edge (holes included)
+    if distance == 0.0 {
+        return multipolygon.clone();
+    }
+
+    let edges = union_all(
+        multipolygon
+            .iter()
+            .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+            .flat_map(|ring| linestring_capsules(ring, distance.abs(), segments))
+            .collect(),
+    );
+
+    match distance > 0.0 {
+        true => return multipolygon.union(&edges),
+        false => return multipolygon.difference(&edges),
+    }
+}
+
+fn linestring_capsules(
+    linestring: &LineString,
+    distance: f64,
+    segments: usize,
+) -> Vec<MultiPolygon> {
+    // Single co-ord lines buffer as a point
+    if linestring.0.len() == 1 {
+        return vec![to_multipolygon(circle(linestring.0[0], distance, segments))];
+    }
+
+    return linestring
+        .lines()
+        .map(|line| to_multipolygon(capsule(&line, distance, segments)))
+        .collect();
+}
+
+fn union_all(mut parts: Vec<MultiPolygon>) -> MultiPolygon {
+    // Pairwise (tree) union keeps each overlay small
+    while parts.len() > 1 {
+        parts = parts
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => left.union(right),
+                [single] => single.clone(),
+                _ => MultiPolygon::new(vec![]),
+            })
+            .collect();
+    }
+
+    return parts.pop().unwrap_or_else(|| MultiPolygon::new(vec![]));
+}
+
+fn circle(centre: Coord, radius: f64, segments: usize) -> Polygon {
+    let segments = segments.max(4);
+    let coords: Vec<Coord> = (0..segments)
+        .map(|idx| {
+            let theta = 2.0 * PI * idx as f64 / segments as f64;
+            Coord {
+                x: centre.x + radius * theta.cos(),
+                y: centre.y + radius * theta.sin(),
+            }
+        })
+        .collect();
+
+    return Polygon::new(LineString::from(coords), vec![]);
+}
+
+fn capsule(line: &Line, radius: f64, segments: usize) -> Polygon {
+    // Segment swept by a circle : half circle caps joined by the offset edges
+    if line.start == line.end {
+        return circle(line.start, radius, segments);
+    }
+
+    let angle = line.dy().atan2(line.dx());
+    let half = (segments.max(4) / 2) as f64;
+    let mut coords: Vec<Coord> = vec![];
+
+    for (centre, start_angle) in [
+        (line.end, angle - FRAC_PI_2),
+        (line.start, angle + FRAC_PI_2),
+    ] {
+        for idx in 0..=(half as usize) {
+            let theta = start_angle + PI * idx as f64 / half;
+            coords.push(Coord {
+                x: centre.x + radius * theta.cos(),
+                y: centre.y + radius * theta.sin(),
+            });
+        }
+    }
+
+    return Polygon::new(LineString::from(coords), vec![]);
+}
+
+fn to_multipolygon(polygon: Polygon) -> MultiPolygon {
+    match polygon.exterior().0.is_empty() {
+        true => return MultiPolygon::new(vec![]),
+        false => return MultiPolygon::new(vec![polygon]),
+    }
+}
+
+#[test]
+fn test_buffer_point() {
+    use crate::dist::point_distance;
+    use geo::{Area, point};
+
+    // Planar circle area approaches pi r2
+    let point: Point = point!(x: 325000.0, y: 673000.0);
+    let circle = buffer_point(&point, 500.0, 128, &DistanceMethod::Planar);
+    let pi_r2 = PI * 500.0 * 500.0;
+    assert!(100.0 * ((circle.unsigned_area() - pi_r2) / pi_r2).abs() < 0.1);
+
+    // Geodesic circle vertices all sit at the buffer distance
+    let edinburgh: Point = point!(x: -3.2007650172960296, y: 55.95042325369335);
+    let circle = buffer_point(
+        &edinburgh,
+        500.0,
+        DEFAULT_SEGMENTS,
+        &DistanceMethod::Geodesic,
+    );
+    assert_eq!(circle.exterior().0.len(), DEFAULT_SEGMENTS + 1);
+    for coord in circle.exterior().coords() {
+        let dist = point_distance(&edinburgh, &Point(*coord), &DistanceMethod::Geodesic);
+        assert!((dist - 500.0).abs() < 0.000_001);
+    }
+
+    assert!(
+        buffer_point(&edinburgh, -500.0, 32, &DistanceMethod::Geodesic)
+            .exterior()
+            .0
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_buffer_linestring() {
+    use geo::{Area, line_string};
+
+    // 1km line, 100m buffer : 2rL rectangle & rounded ends
+    let linestring: LineString = line_string![
+        (x: 325000.0, y: 673000.0),
+        (x: 325500.0, y: 673000.0),
+        (x: 326000.0, y: 673000.0),
+    ];
+    let buffer = buffer_linestring(&linestring, 100.0, 128, &DistanceMethod::Planar);
+    let expected = 2.0 * 100.0 * 1000.0 + PI * 100.0 * 100.0;
+
+    assert_eq!(buffer.0.len(), 1);
+    assert!(100.0 * ((buffer.unsigned_area() - expected) / expected).abs() < 0.1);
+}
+
+#[test]
+fn test_buffer_polygon() {
+    use crate::coord;
+    use crate::measure;
+    use crs_definitions as crs_refs;
+    use geo::{Area, polygon};
+
+    let square: Polygon = polygon![
+        (x: 325000.0, y: 673000.0),
+        (x: 326000.0, y: 673000.0),
+        (x: 326000.0, y: 674000.0),
+        (x: 325000.0, y: 674000.0),
+        (x: 325000.0, y: 673000.0),
+    ];
+
+    // Negative buffers pull the edges in, leaving square corners
+    let shrunk = buffer_polygon(&square, -100.0, DEFAULT_SEGMENTS, &DistanceMethod::Planar);
+    assert!((shrunk.unsigned_area() - 800.0 * 800.0).abs() < 0.001);
+
+    // Positive buffers round the corners
+    let grown = buffer_polygon(&square, 100.0, 128, &DistanceMethod::Planar);
+    let expected = 1_000_000.0 + 4.0 * 100.0 * 1000.0 + PI * 100.0 * 100.0;
+    assert!(100.0 * ((grown.unsigned_area() - expected) / expected).abs() < 0.1);
+
+    // Shrinking past the centre leaves nothing
+    let gone = buffer_polygon(&square, -600.0, DEFAULT_SEGMENTS, &DistanceMethod::Planar);
+    assert!(gone.0.is_empty());
+
+    // Lon / lat buffers are metre based
+    let square_tf = coord::update_poly_crs(&square, &crs_refs::EPSG_27700, &crs_refs::EPSG_4326);
+    let grown_tf = buffer_polygon(&square_tf, 100.0, 128, &DistanceMethod::Geodesic);
+    let area = measure::multipolygon_area(&grown_tf, &DistanceMethod::Geodesic);
+    assert!(100.0 * ((area - expected) / expected).abs() < 0.2);
+}
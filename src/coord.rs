@@ -1,15 +1,79 @@
 // Geospatial Modelling
+use crate::geodb::GeoData;
 use crs_definitions as crs_refs;
-use geo::{Coord, MapCoords, Point, Polygon, point};
+use geo::{Coord, Geometry, MapCoords, Point, Polygon, point};
 use proj4rs::proj::Proj;
 
+pub fn is_geographic(crs: &crs_refs::Def) -> bool {
+    return is_geographic_proj(crs.proj4);
+}
+
+pub fn is_geographic_proj(proj4: &str) -> bool {
+    // Geographic CRSs hold lon / lat degrees rather than projected metres
+    return proj4.contains("+proj=longlat") || proj4.contains("+proj=latlong");
+}
+
+pub fn geographic_proj(proj4: &str) -> String {
+    // Lon / lat on the CRS's own datum (e.g. OSGB36 for BNG), keeping its ellipsoid & WGS84 shift
+    if is_geographic_proj(proj4) {
+        return proj4.to_string();
+    }
+    let datum_params = [
+        "+datum=",
+        "+ellps=",
+        "+a=",
+        "+b=",
+        "+rf=",
+        "+f=",
+        "+towgs84=",
+        "+nadgrids=",
+        "+pm=",
+    ];
+    let mut params = vec!["+proj=longlat"];
+    params.extend(
+        proj4
+            .split_whitespace()
+            .filter(|param| datum_params.iter().any(|prefix| param.starts_with(prefix))),
+    );
+    params.push("+no_defs");
+
+    return params.join(" ");
+}
+
+pub fn copy_crs(crs: &crs_refs::Def) -> crs_refs::Def {
+    // Def holds static strs but isn't Clone
+    return crs_refs::Def {
+        code: crs.code,
+        proj4: crs.proj4,
+        wkt: crs.wkt,
+    };
+}
+
+pub fn update_feature_crs(
+    feature: &GeoData,
+    active_crs: &crs_refs::Def,
+    target_crs: &crs_refs::Def,
+) -> GeoData {
+    // Reprojects the feature's geometry, whatever its type
+    let geometry = feature
+        .geometry
+        .as_ref()
+        .map(|geometry| update_geometry_proj(geometry, active_crs.proj4, target_crs.proj4));
+
+    return GeoData::new(feature.uuid.clone(), geometry, feature.properties.clone());
+}
+
 pub fn update_poly_crs(
     polygon: &Polygon,
     active_crs: &crs_refs::Def,
     target_crs: &crs_refs::Def,
 ) -> Polygon {
+    return update_poly_proj(polygon, active_crs.proj4, target_crs.proj4);
+}
+
+pub fn update_poly_proj(polygon: &Polygon, active_proj4: &str, target_proj4: &str) -> Polygon {
     return polygon.map_coords(|Coord { x, y }| {
-        let new_point: Point = update_point_crs(Point::new(x, y), active_crs, target_crs);
+        let new_point: Point = update_point_proj(Point::new(x, y), active_proj4, target_proj4);
         return Coord {
             x: new_point.x(),
             y: new_point.y(),
@@ -22,22 +86,54 @@ pub fn update_point_crs(
     active_crs: &crs_refs::Def,
     target_crs: &crs_refs::Def,
 ) -> Point {
-    // Setup mutable copy
-    let mut point_mut = (point.x(), point.y());
+    return update_point_proj(point, active_crs.proj4, target_crs.proj4);
+}
+
+pub fn update_point_proj(point: Point, active_proj4: &str, target_proj4: &str) -> Point {
+    // Setup mutable copy - proj4rs works in radians for geographic CRSs
+    let mut point_mut = match is_geographic_proj(active_proj4) {
+        true => (point.x().to_radians(), point.y().to_radians()),
+        false => (point.x(), point.y()),
+    };
 
     // Transform
     let projection = proj4rs::transform::transform(
-        &Proj::from_proj_string(active_crs.proj4).unwrap(),
-        &Proj::from_proj_string(target_crs.proj4).unwrap(),
+        &Proj::from_proj_string(active_proj4).unwrap(),
+        &Proj::from_proj_string(target_proj4).unwrap(),
         &mut point_mut,
     );
 
     match projection {
-        Ok(_) => return point!((point_mut.0.to_degrees(), point_mut.1.to_degrees())),
+        Ok(_) if is_geographic_proj(target_proj4) => {
+            return point!((point_mut.0.to_degrees(), point_mut.1.to_degrees()));
+        }
+        Ok(_) => return point!((point_mut.0, point_mut.1)),
         Err(_) => return point,
     }
 }
 
+pub fn update_geometry_proj(
+    geometry: &Geometry,
+    active_proj4: &str,
+    target_proj4: &str,
+) -> Geometry {
+    return geometry.map_coords(|Coord { x, y }| {
+        let new_point: Point = update_point_proj(Point::new(x, y), active_proj4, target_proj4);
+        return Coord {
+            x: new_point.x(),
+            y: new_point.y(),
+        };
+    });
+}
+
+#[test]
+fn test_is_geographic() {
+    assert!(is_geographic(&crs_refs::EPSG_4326));
+    assert!(is_geographic(&crs_refs::EPSG_4258));
+    assert!(!is_geographic(&crs_refs::EPSG_27700));
+    assert!(!is_geographic(&crs_refs::EPSG_3034));
+}
+
 #[test]
 fn test_update_poly_crs() {
     use geo::polygon;
@@ -90,3 +186,34 @@ fn test_update_point_crs() {
         (point_tf.y() * 1000000.0).round() / 1000000.0
     );
 }
+
+#[test]
+fn test_update_point_crs_round_trip() {
+    // CRS setup
+    let active_crs = crs_refs::EPSG_4326;
+    let target_crs = crs_refs::EPSG_27700;
+
+    // Lon / lat in, BNG metres out & back again
+    let point: Point<f64> = Point::new(-3.2007650172960296, 55.95042325369335);
+    let point_bng = update_point_crs(point, &active_crs, &target_crs);
+    let point_back = update_point_crs(point_bng, &target_crs, &active_crs);
+
+    assert!(point_bng.x() > 300_000.0 && point_bng.x() < 400_000.0);
+    assert!(point_bng.y() > 600_000.0 && point_bng.y() < 700_000.0);
+    // Datum shift inversion is iterative, centimetre level agreement
+    assert!((point_back.x() - point.x()).abs() < 1e-7);
+    assert!((point_back.y() - point.y()).abs() < 1e-7);
+}
+
+#[test]
+fn test_geographic_proj() {
+    // BNG's base geographic CRS is OSGB36 lon / lat
+    assert_eq!(
+        geographic_proj(crs_refs::EPSG_27700.proj4),
+        crs_refs::EPSG_4277.proj4
+    );
+    assert_eq!(
+        geographic_proj(crs_refs::EPSG_4326.proj4),
+        crs_refs::EPSG_4326.proj4
+    );
+}
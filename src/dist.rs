@@ -1,11 +1,142 @@
 // Geospatial Distance Calculations
-use geo::{Closest, ClosestPoint, Coord, Point, Polygon};
-use geo::{Distance, Geodesic, Haversine};
+use crate::coord;
+use crate::geodb::GeoData;
+use crate::index;
+use crate::measure;
+use crs_definitions as crs_refs;
+use geo::line_intersection::{LineIntersection, line_intersection};
+use geo::{Bearing, BooleanOps, BoundingRect, CoordsIter, Destination, Intersects, LinesIter};
+use geo::{Closest, ClosestPoint, Contains, Coord, Geometry, Line, LineString, MultiLineString};
+use geo::{Distance, Euclidean, Geodesic, Haversine, InterpolatePoint, Rhumb, VincentyDistance};
+use geo::{MultiPolygon, Point, Polygon, point};
+use geographiclib_rs::{DirectGeodesic, InverseGeodesic};
+use rayon::prelude::*;
+use rstar::{AABB, RTree};
+use std::io::Write;
+
+// Mean earth radius (m) used by geo's Haversine
+pub const MEAN_EARTH_RADIUS: f64 = 6_371_008.8;
+
+// Rows of the from dataset held in memory per parallel distance matrix batch
+const MATRIX_BATCH_ROWS: usize = 256;
+
+// Obstacle edge indexed for over-water path visibility tests
+type Edge = rstar::primitives::Line<[f64; 2]>;
+
+// Ring edges either side of a corner checked before the indexed visibility test
+const NEARBY_EDGES: usize = 8;
+
+// Golden-section search iterations when locating the closest point on an edge
+const EDGE_SEARCH_ITERATIONS: usize = 64;
 
 #[derive(Debug, PartialEq)]
 pub enum DistanceMethod {
     Haversine,
     Geodesic,
+    Vincenty,
+    Rhumb,
+    Planar,
+    HaversineRadius(f64),
+    GeodesicEllipsoid(Ellipsoid),
+}
+
+impl DistanceMethod {
+    pub fn is_geographic(&self) -> bool {
+        // All methods other than planar measure on lon / lat degrees
+        return !matches!(self, DistanceMethod::Planar);
+    }
+
+    pub fn ellipsoid(&self) -> Option<Ellipsoid> {
+        // Earth model behind the method - spherical methods use a sphere, rhumb & vincenty WGS84
+        match self {
+            DistanceMethod::Planar => return None,
+            DistanceMethod::Haversine => return Some(Ellipsoid::new(MEAN_EARTH_RADIUS, 0.0)),
+            DistanceMethod::HaversineRadius(radius) => return Some(Ellipsoid::new(*radius, 0.0)),
+            DistanceMethod::Geodesic | DistanceMethod::Vincenty | DistanceMethod::Rhumb => {
+                return Some(Ellipsoid::WGS84);
+            }
+            DistanceMethod::GeodesicEllipsoid(ellipsoid) => return Some(*ellipsoid),
+        }
+    }
+
+    pub fn geodesic_from_crs(crs: &crs_refs::Def) -> DistanceMethod {
+        // Geodesic on the CRS ellipsoid, WGS84 if it can't be read
+        match Ellipsoid::from_crs(crs) {
+            Some(ellipsoid) => return DistanceMethod::GeodesicEllipsoid(ellipsoid),
+            None => return DistanceMethod::Geodesic,
+        }
+    }
+
+    pub fn haversine_from_crs(crs: &crs_refs::Def) -> DistanceMethod {
+        // Haversine on the mean radius of the CRS ellipsoid
+        match Ellipsoid::from_crs(crs) {
+            Some(ellipsoid) => return DistanceMethod::HaversineRadius(ellipsoid.mean_radius()),
+            None => return DistanceMethod::Haversine,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    pub semi_major_axis: f64,
+    pub flattening: f64,
+}
+
+impl Ellipsoid {
+    pub const WGS84: Ellipsoid = Ellipsoid {
+        semi_major_axis: 6_378_137.0,
+        flattening: 1.0 / 298.257223563,
+    };
+
+    pub const GRS80: Ellipsoid = Ellipsoid {
+        semi_major_axis: 6_378_137.0,
+        flattening: 1.0 / 298.257222101,
+    };
+
+    pub const AIRY_1830: Ellipsoid = Ellipsoid {
+        semi_major_axis: 6_377_563.396,
+        flattening: 1.0 / 299.3249646,
+    };
+
+    pub fn new(semi_major_axis: f64, inverse_flattening: f64) -> Ellipsoid {
+        // Inverse flattening of zero denotes a sphere (as in WKT)
+        let flattening = match inverse_flattening {
+            0.0 => 0.0,
+            _ => 1.0 / inverse_flattening,
+        };
+
+        return Ellipsoid {
+            semi_major_axis,
+            flattening,
+        };
+    }
+
+    pub fn from_crs(crs: &crs_refs::Def) -> Option<Ellipsoid> {
+        // WKT holds the spheroid for both geographic & projected CRSs : SPHEROID["name",a,rf,...]
+        let spheroid = crs.wkt.split("SPHEROID[").nth(1)?;
+        let mut params = spheroid
+            .split(',')
+            .skip(1)
+            .map(|val| val.trim_end_matches(']').trim().parse::<f64>());
+
+        let semi_major_axis = params.next()?.ok()?;
+        let inverse_flattening = params.next()?.ok()?;
+
+        return Some(Ellipsoid::new(semi_major_axis, inverse_flattening));
+    }
+
+    pub fn semi_minor_axis(&self) -> f64 {
+        return self.semi_major_axis * (1.0 - self.flattening);
+    }
+
+    pub fn mean_radius(&self) -> f64 {
+        // IUGG mean radius (2a + b) / 3
+        return (2.0 * self.semi_major_axis + self.semi_minor_axis()) / 3.0;
+    }
+
+    fn geodesic(&self) -> geographiclib_rs::Geodesic {
+        return geographiclib_rs::Geodesic::new(self.semi_major_axis, self.flattening);
+    }
 }
 
 pub fn find_closest_point(point: &Point, polygon: &Polygon) -> Point {
@@ -23,13 +154,209 @@ pub fn find_closest_point(point: &Point, polygon: &Polygon) -> Point {
     };
 }
 
+pub fn find_closest_point_on_surface(
+    point: &Point,
+    polygon: &Polygon,
+    method: &DistanceMethod,
+) -> Point {
+    // Points inside the polygon are their own closest point (as with find_closest_point)
+    if polygon.contains(point) {
+        return *point;
+    }
+
+    let mut closest: Option<(Point, f64)> = None;
+    for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+        for line in ring.lines() {
+            let candidate = closest_point_on_edge(point, &line, method);
+            let dist = point_distance(point, &candidate, method);
+
+            if closest.is_none_or(|(_, best)| dist < best) {
+                closest = Some((candidate, dist));
+            }
+        }
+    }
+
+    match closest {
+        Some((closest_point, _)) => return closest_point,
+        None => return Point::new(0.0, 0.0),
+    }
+}
+
+pub fn closest_point_on_edge(point: &Point, edge: &Line, method: &DistanceMethod) -> Point {
+    // Distance from a point to a geodesic / great-circle edge is unimodal along the edge,
+    // so a golden-section search over the edge ratio converges on the minimum
+    let (start, end) = (Point(edge.start), Point(edge.end));
+    let inv_phi = (5f64.sqrt() - 1.0) / 2.0;
+
+    let dist_at =
+        |ratio: f64| point_distance(point, &point_at_ratio(&start, &end, ratio, method), method);
+
+    let (mut lower, mut upper) = (0.0, 1.0);
+    let mut left = upper - inv_phi * (upper - lower);
+    let mut right = lower + inv_phi * (upper - lower);
+    let (mut left_dist, mut right_dist) = (dist_at(left), dist_at(right));
+
+    for _ in 0..EDGE_SEARCH_ITERATIONS {
+        if left_dist < right_dist {
+            upper = right;
+            right = left;
+            right_dist = left_dist;
+            left = upper - inv_phi * (upper - lower);
+            left_dist = dist_at(left);
+        } else {
+            lower = left;
+            left = right;
+            left_dist = right_dist;
+            right = lower + inv_phi * (upper - lower);
+            right_dist = dist_at(right);
+        }
+    }
+
+    // Edge end points may be closer than the interior minimum found
+    let best = [0.0, (lower + upper) / 2.0, 1.0]
+        .into_iter()
+        .map(|ratio| point_at_ratio(&start, &end, ratio, method))
+        .min_by(|a, b| {
+            point_distance(point, a, method).total_cmp(&point_distance(point, b, method))
+        });
+
+    return best.unwrap_or(start);
+}
+
+pub fn point_at_ratio(start: &Point, end: &Point, ratio: f64, method: &DistanceMethod) -> Point {
+    match method {
+        DistanceMethod::Haversine | DistanceMethod::HaversineRadius(_) => {
+            return Haversine::point_at_ratio_between(*start, *end, ratio);
+        }
+        DistanceMethod::Geodesic | DistanceMethod::Vincenty => {
+            return Geodesic::point_at_ratio_between(*start, *end, ratio);
+        }
+        DistanceMethod::Rhumb => return Rhumb::point_at_ratio_between(*start, *end, ratio),
+        DistanceMethod::Planar => return Euclidean::point_at_ratio_between(*start, *end, ratio),
+        DistanceMethod::GeodesicEllipsoid(ellipsoid) => {
+            let geodesic = ellipsoid.geodesic();
+            let (s12, azi1, _, _): (f64, f64, f64, f64) =
+                geodesic.inverse(start.y(), start.x(), end.y(), end.x());
+            let (lat, lon): (f64, f64) = geodesic.direct(start.y(), start.x(), azi1, s12 * ratio);
+
+            return point!(x: lon, y: lat);
+        }
+    }
+}
+
 pub fn point_distance(point: &Point, to_point: &Point, method: &DistanceMethod) -> f64 {
     match method {
-        &DistanceMethod::Haversine => return Haversine::distance(point.clone(), to_point.clone()),
-        &DistanceMethod::Geodesic => return Geodesic::distance(point.clone(), to_point.clone()),
+        DistanceMethod::Haversine => return Haversine::distance(*point, *to_point),
+        DistanceMethod::Geodesic => return Geodesic::distance(*point, *to_point),
+        DistanceMethod::Vincenty => {
+            // Vincenty fails to converge for near antipodal points, fall back to Karney
+            return point
+                .vincenty_distance(to_point)
+                .unwrap_or_else(|_| Geodesic::distance(*point, *to_point));
+        }
+        DistanceMethod::Rhumb => return Rhumb::distance(*point, *to_point),
+        DistanceMethod::Planar => return Euclidean::distance(*point, *to_point),
+        DistanceMethod::HaversineRadius(radius) => {
+            // Haversine distance scales linearly with the sphere radius
+            return Haversine::distance(*point, *to_point) * (radius / MEAN_EARTH_RADIUS);
+        }
+        DistanceMethod::GeodesicEllipsoid(ellipsoid) => {
+            return ellipsoid
+                .geodesic()
+                .inverse(point.y(), point.x(), to_point.y(), to_point.x());
+        }
+    }
+}
+
+pub fn point_to_method_crs(
+    point: &Point,
+    crs: &crs_refs::Def,
+    method: &DistanceMethod,
+) -> Result<Point, String> {
+    // Geographic methods auto-convert projected data to lon / lat, planar refuses lon / lat
+    match (method.is_geographic(), coord::is_geographic(crs)) {
+        (true, false) => {
+            let target = method_geographic_proj(crs, method);
+            return Ok(coord::update_point_proj(*point, crs.proj4, &target));
+        }
+        (false, true) => return Err(planar_crs_error(crs)),
+        _ => return Ok(*point),
+    }
+}
+
+pub fn polygon_to_method_crs(
+    polygon: &Polygon,
+    crs: &crs_refs::Def,
+    method: &DistanceMethod,
+) -> Result<Polygon, String> {
+    match (method.is_geographic(), coord::is_geographic(crs)) {
+        (true, false) => {
+            let target = method_geographic_proj(crs, method);
+            return Ok(coord::update_poly_proj(polygon, crs.proj4, &target));
+        }
+        (false, true) => return Err(planar_crs_error(crs)),
+        _ => return Ok(polygon.clone()),
     }
 }
 
+fn method_geographic_proj(crs: &crs_refs::Def, method: &DistanceMethod) -> String {
+    // Lon / lat on the CRS's datum when the method measures on its ellipsoid (e.g. OSGB36 on
+    // Airy for BNG), so a single datum is used throughout - otherwise WGS84 as geo expects
+    let crs_ellipsoid = Ellipsoid::from_crs(crs);
+    let same_datum = match method {
+        DistanceMethod::GeodesicEllipsoid(ellipsoid) => crs_ellipsoid == Some(*ellipsoid),
+        DistanceMethod::HaversineRadius(radius) => {
+            crs_ellipsoid.is_some_and(|ellipsoid| ellipsoid.mean_radius() == *radius)
+        }
+        _ => false,
+    };
+
+    match same_datum {
+        true => return coord::geographic_proj(crs.proj4),
+        false => return crs_refs::EPSG_4326.proj4.to_string(),
+    }
+}
+
+fn planar_crs_error(crs: &crs_refs::Def) -> String {
+    return format!(
+        "planar distance requires a projected CRS, EPSG:{} is geographic",
+        crs.code
+    );
+}
+
+pub fn point_distance_crs(
+    point: &Point,
+    to_point: &Point,
+    crs: &crs_refs::Def,
+    method: &DistanceMethod,
+) -> Result<f64, String> {
+    let point = point_to_method_crs(point, crs, method)?;
+    let to_point = point_to_method_crs(to_point, crs, method)?;
+    return Ok(point_distance(&point, &to_point, method));
+}
+
+pub fn point_polygon_distance_crs(
+    point: &Point,
+    to_polygon: &Polygon,
+    crs: &crs_refs::Def,
+    method: &DistanceMethod,
+) -> Result<f64, String> {
+    let point = point_to_method_crs(point, crs, method)?;
+    let to_polygon = polygon_to_method_crs(to_polygon, crs, method)?;
+    return Ok(point_polygon_distance(&point, &to_polygon, method));
+}
+
+pub fn polygon_distance_crs(
+    polygon: &Polygon,
+    to_polygon: &Polygon,
+    crs: &crs_refs::Def,
+    method: &DistanceMethod,
+) -> Result<f64, String> {
+    let polygon = polygon_to_method_crs(polygon, crs, method)?;
+    let to_polygon = polygon_to_method_crs(to_polygon, crs, method)?;
+    return Ok(polygon_distance(&polygon, &to_polygon, method));
+}
+
 pub fn point_polygon_distance(point: &Point, to_polygon: &Polygon, method: &DistanceMethod) -> f64 {
     let to_point = find_closest_point(point, to_polygon);
     return point_distance(point, &to_point, method);
@@ -41,7 +368,7 @@ pub fn polygon_distance(polygon: &Polygon, to_polygon: &Polygon, method: &Distan
     let mut dist_map: Vec<(Point, Point, f64)> = vec![];
 
     for point in poly_vec {
-        let closest = find_closest_point(&Point(point), &polygon);
+        let closest = find_closest_point(&Point(point), polygon);
         let dist = point_distance(&Point(point), &closest, method);
 
         dist_map.push((Point(point), closest, dist));
@@ -57,6 +384,627 @@ pub fn polygon_distance(polygon: &Polygon, to_polygon: &Polygon, method: &Distan
     return closest.2;
 }
 
+pub fn feature_distance(feature: &GeoData, to_feature: &GeoData, method: &DistanceMethod) -> f64 {
+    // Distance (m) between two features of any geometry type, infinite when either has none
+    match &to_feature.geometry {
+        Some(geometry) => return feature.distance_to_geometry(geometry, method),
+        None => return f64::INFINITY,
+    }
+}
+
+pub fn point_geometry_distance(point: &Point, geometry: &Geometry, method: &DistanceMethod) -> f64 {
+    // Distance from point to the nearest part of any geometry, zero when contained
+    let nearest = |dists: Vec<f64>| dists.into_iter().fold(f64::INFINITY, f64::min);
+    let nearest_edge = |lines: Vec<Line>| {
+        nearest(
+            lines
+                .iter()
+                .map(|line| {
+                    point_distance(point, &closest_point_on_edge(point, line, method), method)
+                })
+                .collect(),
+        )
+    };
+
+    match geometry {
+        Geometry::Point(to_point) => return point_distance(point, to_point, method),
+        Geometry::Line(line) => return nearest_edge(vec![*line]),
+        Geometry::LineString(linestring) => return nearest_edge(linestring.lines().collect()),
+        Geometry::Polygon(polygon) => {
+            let closest = find_closest_point_on_surface(point, polygon, method);
+            return point_distance(point, &closest, method);
+        }
+        Geometry::MultiPoint(points) => {
+            return nearest(
+                points
+                    .iter()
+                    .map(|to_point| point_distance(point, to_point, method))
+                    .collect(),
+            );
+        }
+        Geometry::MultiLineString(linestrings) => {
+            return nearest_edge(
+                linestrings
+                    .iter()
+                    .flat_map(|linestring| linestring.lines())
+                    .collect(),
+            );
+        }
+        Geometry::MultiPolygon(polygons) => {
+            return nearest(
+                polygons
+                    .iter()
+                    .map(|polygon| {
+                        point_geometry_distance(point, &Geometry::Polygon(polygon.clone()), method)
+                    })
+                    .collect(),
+            );
+        }
+        Geometry::GeometryCollection(collection) => {
+            return nearest(
+                collection
+                    .iter()
+                    .map(|geometry| point_geometry_distance(point, geometry, method))
+                    .collect(),
+            );
+        }
+        Geometry::Rect(rect) => {
+            return point_geometry_distance(point, &Geometry::Polygon(rect.to_polygon()), method);
+        }
+        Geometry::Triangle(triangle) => {
+            return point_geometry_distance(
+                point,
+                &Geometry::Polygon(triangle.to_polygon()),
+                method,
+            );
+        }
+    }
+}
+
+pub fn closest_point_on_edges(
+    point: &Point,
+    geometry: &Geometry,
+    method: &DistanceMethod,
+) -> Option<(Point, f64)> {
+    // Closest point on any line / ring edge of the geometry (interiors ignored) & its distance (m)
+    let mut closest: Option<(Point, f64)> = None;
+    for edge in geometry_edges(geometry) {
+        let candidate = closest_point_on_edge(point, &edge, method);
+        let dist = point_distance(point, &candidate, method);
+
+        if closest.is_none_or(|(_, best)| dist < best) {
+            closest = Some((candidate, dist));
+        }
+    }
+
+    return closest;
+}
+
+fn geometry_edges(geometry: &Geometry) -> Vec<Line> {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => return vec![],
+        Geometry::Line(line) => return vec![*line],
+        Geometry::LineString(linestring) => return linestring.lines_iter().collect(),
+        Geometry::MultiLineString(linestrings) => return linestrings.lines_iter().collect(),
+        Geometry::Polygon(polygon) => return polygon.lines_iter().collect(),
+        Geometry::MultiPolygon(polygons) => return polygons.lines_iter().collect(),
+        Geometry::Rect(rect) => return rect.lines_iter().collect(),
+        Geometry::Triangle(triangle) => return triangle.lines_iter().collect(),
+        Geometry::GeometryCollection(collection) => {
+            return collection.iter().flat_map(geometry_edges).collect();
+        }
+    }
+}
+
+pub fn point_polygon_signed_distance(
+    point: &Point,
+    polygon: &Polygon,
+    method: &DistanceMethod,
+) -> f64 {
+    return point_multipolygon_signed_distance(
+        point,
+        &MultiPolygon::new(vec![polygon.clone()]),
+        method,
+    );
+}
+
+pub fn point_multipolygon_signed_distance(
+    point: &Point,
+    multipolygon: &MultiPolygon,
+    method: &DistanceMethod,
+) -> f64 {
+    // Distance (m) to the nearest boundary (holes included), negative when the point is inside
+    let boundary_dist = multipolygon
+        .iter()
+        .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+        .flat_map(|ring| ring.lines())
+        .map(|line| point_distance(point, &closest_point_on_edge(point, &line, method), method))
+        .fold(f64::INFINITY, f64::min);
+
+    // Points in a hole are outside the polygon
+    match multipolygon.contains(point) {
+        true => return -boundary_dist,
+        false => return boundary_dist,
+    }
+}
+
+pub fn geometry_distance(
+    geometry: &Geometry,
+    to_geometry: &Geometry,
+    method: &DistanceMethod,
+) -> f64 {
+    // Distance between the nearest parts of two geometries, zero when they touch or overlap
+    if let Geometry::Point(point) = geometry {
+        return point_geometry_distance(point, to_geometry, method);
+    }
+    if let Geometry::Point(point) = to_geometry {
+        return point_geometry_distance(point, geometry, method);
+    }
+    if geometry.intersects(to_geometry) {
+        return 0.0;
+    }
+
+    // Closest approach of separate geometries always involves a vertex of one of them
+    let from = geometry
+        .coords_iter()
+        .map(|coord| point_geometry_distance(&Point(coord), to_geometry, method));
+    let to = to_geometry
+        .coords_iter()
+        .map(|coord| point_geometry_distance(&Point(coord), geometry, method));
+
+    return from.chain(to).fold(f64::INFINITY, f64::min);
+}
+
+pub fn write_distance_matrix<W: Write>(
+    from: &[GeoData],
+    to: &[GeoData],
+    method: &DistanceMethod,
+    max_distance: Option<f64>,
+    writer: W,
+) -> std::io::Result<usize> {
+    // Stream every from -> to distance (m) as CSV, optionally only those within max_distance
+    // Features without a geometry have no distance & are skipped on both sides
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["from_uuid", "to_uuid", "distance"])?;
+
+    let mut rows_written = 0;
+    let from: Vec<&GeoData> = from.iter().filter(|row| row.geometry.is_some()).collect();
+
+    // With a threshold only to features whose bbox is within range are measured
+    let tree = max_distance.map(|_| index::envelope_tree(to));
+
+    // Batches bound memory to MATRIX_BATCH_ROWS x to.len() distances
+    for batch in from.chunks(MATRIX_BATCH_ROWS) {
+        let distances: Vec<Vec<(usize, f64)>> = batch
+            .par_iter()
+            .map(|from_row| {
+                let geometry = from_row
+                    .geometry
+                    .as_ref()
+                    .expect("features filtered on geometry");
+                let candidates: Vec<usize> = match (&tree, max_distance) {
+                    (Some(tree), Some(max_distance)) => {
+                        match index::search_rect(geometry, max_distance, method) {
+                            Some(rect) => {
+                                let mut candidates: Vec<usize> =
+                                    index::locate_rect(tree, &rect).collect();
+                                candidates.sort_unstable();
+                                candidates
+                            }
+                            None => vec![],
+                        }
+                    }
+                    _ => (0..to.len()).collect(),
+                };
+
+                candidates
+                    .into_iter()
+                    .filter_map(|idx| {
+                        let dist =
+                            from_row.distance_to_geometry(to[idx].geometry.as_ref()?, method);
+                        match max_distance {
+                            Some(max_distance) if dist > max_distance => None,
+                            _ => Some((idx, dist)),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for (from_row, row_distances) in batch.iter().zip(distances) {
+            for (idx, dist) in row_distances {
+                csv_writer.write_record([&from_row.uuid, &to[idx].uuid, &dist.to_string()])?;
+                rows_written += 1;
+            }
+        }
+    }
+
+    csv_writer.flush()?;
+    return Ok(rows_written);
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Crossing {
+    pub uuid: String,
+    pub inside: MultiLineString,
+    pub length: f64,
+}
+
+pub fn path_crossings(
+    path: &LineString,
+    data: &[GeoData],
+    method: &DistanceMethod,
+) -> Vec<Crossing> {
+    // Polygon features the path passes through & the length (m) of path inside each
+    // Clipping is straight line in co-ord space - densify geodesic paths with path_linestring
+    let path_rect = match path.bounding_rect() {
+        Some(rect) => rect,
+        None => return vec![],
+    };
+    let paths = MultiLineString::new(vec![path.clone()]);
+
+    return data
+        .iter()
+        .filter_map(|row| {
+            if !row.bounding_rect()?.intersects(&path_rect) {
+                return None;
+            }
+            let inside = match row.geometry.as_ref()? {
+                Geometry::Polygon(polygon) => polygon.clip(&paths, false),
+                Geometry::MultiPolygon(multipolygon) => multipolygon.clip(&paths, false),
+                _ => return None,
+            };
+
+            let length = measure::multilinestring_length(&inside, method);
+            match length > 0.0 {
+                true => Some(Crossing {
+                    uuid: row.uuid.clone(),
+                    inside,
+                    length,
+                }),
+                false => None,
+            }
+        })
+        .collect();
+}
+
+pub fn initial_bearing(point: &Point, to_point: &Point, method: &DistanceMethod) -> f64 {
+    // Degrees clockwise from north (or grid north for planar) in [0, 360)
+    let bearing = match method {
+        DistanceMethod::Haversine | DistanceMethod::HaversineRadius(_) => {
+            Haversine::bearing(*point, *to_point)
+        }
+        DistanceMethod::Rhumb => Rhumb::bearing(*point, *to_point),
+        DistanceMethod::Planar => (to_point.x() - point.x())
+            .atan2(to_point.y() - point.y())
+            .to_degrees(),
+        _ => {
+            let (_, azi1, _, _): (f64, f64, f64, f64) =
+                geodesic_for(method).inverse(point.y(), point.x(), to_point.y(), to_point.x());
+            azi1
+        }
+    };
+
+    return bearing.rem_euclid(360.0);
+}
+
+pub fn final_bearing(point: &Point, to_point: &Point, method: &DistanceMethod) -> f64 {
+    // Bearing of travel on arrival at to_point
+    let bearing = match method {
+        DistanceMethod::Haversine | DistanceMethod::HaversineRadius(_) => {
+            Haversine::bearing(*to_point, *point) + 180.0
+        }
+        DistanceMethod::Rhumb | DistanceMethod::Planar => initial_bearing(point, to_point, method),
+        _ => {
+            let (_, _, azi2, _): (f64, f64, f64, f64) =
+                geodesic_for(method).inverse(point.y(), point.x(), to_point.y(), to_point.x());
+            azi2
+        }
+    };
+
+    return bearing.rem_euclid(360.0);
+}
+
+pub fn destination_point(
+    point: &Point,
+    bearing: f64,
+    distance: f64,
+    method: &DistanceMethod,
+) -> Point {
+    // Point reached after travelling distance (m) from point on the given bearing (deg)
+    match method {
+        DistanceMethod::Haversine => return Haversine::destination(*point, bearing, distance),
+        DistanceMethod::HaversineRadius(radius) => {
+            // Same angular distance on the mean radius sphere
+            let scaled_distance = distance * (MEAN_EARTH_RADIUS / radius);
+            return Haversine::destination(*point, bearing, scaled_distance);
+        }
+        DistanceMethod::Rhumb => return Rhumb::destination(*point, bearing, distance),
+        DistanceMethod::Planar => {
+            let (sin, cos) = bearing.to_radians().sin_cos();
+            return point!(x: point.x() + distance * sin, y: point.y() + distance * cos);
+        }
+        _ => {
+            let (lat, lon): (f64, f64) =
+                geodesic_for(method).direct(point.y(), point.x(), bearing, distance);
+            return point!(x: lon, y: lat);
+        }
+    }
+}
+
+pub fn intermediate_points(
+    point: &Point,
+    to_point: &Point,
+    count: usize,
+    method: &DistanceMethod,
+) -> Vec<Point> {
+    // Evenly spaced points strictly between point & to_point along the method's path
+    return (1..=count)
+        .map(|idx| {
+            let ratio = idx as f64 / (count + 1) as f64;
+            point_at_ratio(point, to_point, ratio, method)
+        })
+        .collect();
+}
+
+pub fn path_linestring(
+    point: &Point,
+    to_point: &Point,
+    max_segment_length: f64,
+    method: &DistanceMethod,
+) -> LineString {
+    // Great circle / geodesic path with no segment longer than max_segment_length (m)
+    let distance = point_distance(point, to_point, method);
+    let count = match max_segment_length > 0.0 {
+        true => (distance / max_segment_length).ceil() as usize,
+        false => 1,
+    };
+
+    let mut coords = vec![*point];
+    coords.extend(intermediate_points(
+        point,
+        to_point,
+        count.saturating_sub(1),
+        method,
+    ));
+    coords.push(*to_point);
+
+    return LineString::from(coords);
+}
+
+pub fn obstacle_path(
+    point: &Point,
+    to_point: &Point,
+    obstacles: &MultiPolygon,
+    method: &DistanceMethod,
+) -> Option<(LineString, f64)> {
+    // Shortest path (m) between two points that doesn't cross any obstacle (eg. a land mask)
+    // A* over a visibility graph of the obstacle corners, None when either end is inside an obstacle
+    if obstacles.contains(point) || obstacles.contains(to_point) {
+        return None;
+    }
+
+    // Shortest paths only bend at corners jutting out into open space, kept by ring & position
+    let rings: Vec<&[Coord]> = obstacles
+        .iter()
+        .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+        .map(|ring| &ring.0[..ring.0.len().saturating_sub(1)])
+        .collect();
+    let exteriors: Vec<bool> = obstacles
+        .iter()
+        .flat_map(|polygon| std::iter::once(true).chain(polygon.interiors().iter().map(|_| false)))
+        .collect();
+
+    let mut nodes: Vec<Point> = vec![*point, *to_point];
+    let mut corners: Vec<Option<(usize, usize)>> = vec![None, None];
+    let mut edges: Vec<Edge> = vec![];
+    for (ring_idx, coords) in rings.iter().enumerate() {
+        if coords.len() < 3 {
+            continue;
+        }
+
+        // Exterior corners turn with the ring's winding, corners of holes against it
+        let winding: f64 = (0..coords.len())
+            .map(|idx| {
+                let (start, end) = (coords[idx], coords[(idx + 1) % coords.len()]);
+                start.x * end.y - end.x * start.y
+            })
+            .sum();
+        let sign = match exteriors[ring_idx] {
+            true => winding.signum(),
+            false => -winding.signum(),
+        };
+        for idx in 0..coords.len() {
+            let (prev, next) = ring_neighbours(coords, idx);
+            if cross(prev, coords[idx], next) * sign > 0.0 {
+                nodes.push(Point(coords[idx]));
+                corners.push(Some((ring_idx, idx)));
+            }
+            edges.push(Edge::new(
+                coords[idx].into(),
+                coords[(idx + 1) % coords.len()].into(),
+            ));
+        }
+    }
+    let edges = RTree::bulk_load(edges);
+
+    // Straight line distance to the destination never overestimates, so A* stays exact
+    let heuristic: Vec<f64> = nodes
+        .iter()
+        .map(|node| point_distance(node, to_point, method))
+        .collect();
+    let mut dists: Vec<f64> = vec![f64::INFINITY; nodes.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; nodes.len()];
+    let mut done: Vec<bool> = vec![false; nodes.len()];
+    dists[0] = 0.0;
+
+    loop {
+        // No reachable node left means the obstacles wall off the destination
+        let current = (0..nodes.len())
+            .filter(|idx| !done[*idx] && dists[*idx].is_finite())
+            .min_by(|a, b| (dists[*a] + heuristic[*a]).total_cmp(&(dists[*b] + heuristic[*b])))?;
+        if current == 1 {
+            break;
+        }
+        done[current] = true;
+
+        for next in 0..nodes.len() {
+            if done[next] || nodes[next] == nodes[current] {
+                continue;
+            }
+            // A taut path only wraps around corners, cheap checks before the visibility test
+            let line = Line::new(nodes[current], nodes[next]);
+            if !is_tangent(&line, &rings, corners[next])
+                || !is_tangent(&line, &rings, corners[current])
+            {
+                continue;
+            }
+            let dist = dists[current] + point_distance(&nodes[current], &nodes[next], method);
+            if dist < dists[next]
+                && !is_blocked_nearby(&line, &rings, corners[current])
+                && !is_blocked_nearby(&line, &rings, corners[next])
+                && is_visible(&line, &edges)
+            {
+                dists[next] = dist;
+                previous[next] = Some(current);
+            }
+        }
+    }
+
+    let mut coords: Vec<Coord> = vec![nodes[1].0];
+    let mut node = 1;
+    while let Some(prev) = previous[node] {
+        coords.push(nodes[prev].0);
+        node = prev;
+    }
+    coords.reverse();
+
+    return Some((LineString::new(coords), dists[1]));
+}
+
+fn cross(a: Coord, b: Coord, c: Coord) -> f64 {
+    // Turn at b going a -> b -> c, positive to the left
+    return (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x);
+}
+
+fn ring_neighbours(coords: &[Coord], idx: usize) -> (Coord, Coord) {
+    // Vertices either side of idx on an open ring
+    return (
+        coords[(idx + coords.len() - 1) % coords.len()],
+        coords[(idx + 1) % coords.len()],
+    );
+}
+
+fn is_tangent(line: &Line, rings: &[&[Coord]], corner: Option<(usize, usize)>) -> bool {
+    // Both of the corner's neighbouring vertices on the same side of the line
+    let (ring_idx, idx) = match corner {
+        Some(corner) => corner,
+        None => return true,
+    };
+    let (prev, next) = ring_neighbours(rings[ring_idx], idx);
+    let side =
+        |coord: Coord| line.dx() * (coord.y - line.start.y) - line.dy() * (coord.x - line.start.x);
+    return side(prev) * side(next) >= 0.0;
+}
+
+fn is_blocked_nearby(line: &Line, rings: &[&[Coord]], corner: Option<(usize, usize)>) -> bool {
+    // Sight lines along a jagged coast are mostly cut by the next few headlands either side
+    let (ring_idx, idx) = match corner {
+        Some(corner) => corner,
+        None => return false,
+    };
+    let coords = rings[ring_idx];
+    let window = NEARBY_EDGES.min(coords.len() / 2);
+    for offset in 0..2 * window {
+        let start = (idx + coords.len() - window + offset) % coords.len();
+        let edge = Line::new(coords[start], coords[(start + 1) % coords.len()]);
+        if let Some(LineIntersection::SinglePoint {
+            is_proper: true, ..
+        }) = line_intersection(*line, edge)
+        {
+            return true;
+        }
+    }
+    return false;
+}
+
+fn is_visible(line: &Line, edges: &RTree<Edge>) -> bool {
+    // Line doesn't pass through the interior of any obstacle, running along edges is fine
+    // Checked in co-ord space, so long lon / lat legs are treated as straight lines
+    let rect = line.bounding_rect();
+    let envelope = AABB::from_corners(rect.min().into(), rect.max().into());
+    let length_2 = line.dx() * line.dx() + line.dy() * line.dy();
+
+    // Split the line where it meets the boundary, then test the middle of each piece
+    let mut ratios: Vec<f64> = vec![0.0, 1.0];
+    for edge in edges.locate_in_envelope_intersecting(&envelope) {
+        let edge = Line::new(Coord::from(edge.from), Coord::from(edge.to));
+        match line_intersection(*line, edge) {
+            Some(LineIntersection::SinglePoint {
+                is_proper: true, ..
+            }) => return false,
+            Some(LineIntersection::SinglePoint { intersection, .. }) => {
+                ratios.push(line_ratio(line, intersection, length_2));
+            }
+            Some(LineIntersection::Collinear { intersection }) => {
+                ratios.push(line_ratio(line, intersection.start, length_2));
+                ratios.push(line_ratio(line, intersection.end, length_2));
+            }
+            None => (),
+        }
+    }
+
+    ratios.sort_by(|a, b| a.total_cmp(b));
+    for pair in ratios.windows(2) {
+        if pair[1] - pair[0] < 1e-12 {
+            continue;
+        }
+        let mid = (pair[0] + pair[1]) / 2.0;
+        let mid_coord = Coord {
+            x: line.start.x + mid * line.dx(),
+            y: line.start.y + mid * line.dy(),
+        };
+        if is_inside(mid_coord, edges) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+fn is_inside(coord: Coord, edges: &RTree<Edge>) -> bool {
+    // Crossing number of a ray east from the point, only edges level with the ray are looked at
+    let max_x = edges.root().envelope().upper()[0];
+    if coord.x > max_x {
+        return false;
+    }
+    let ray = AABB::from_corners([coord.x, coord.y], [max_x, coord.y]);
+    let crossings = edges
+        .locate_in_envelope_intersecting(&ray)
+        .filter(|edge| {
+            let (start, end) = (edge.from, edge.to);
+            (start[1] > coord.y) != (end[1] > coord.y)
+                && coord.x
+                    < start[0] + (coord.y - start[1]) * (end[0] - start[0]) / (end[1] - start[1])
+        })
+        .count();
+    return crossings % 2 == 1;
+}
+
+fn line_ratio(line: &Line, coord: Coord, length_2: f64) -> f64 {
+    let ratio =
+        ((coord.x - line.start.x) * line.dx() + (coord.y - line.start.y) * line.dy()) / length_2;
+    return ratio.clamp(0.0, 1.0);
+}
+
+fn geodesic_for(method: &DistanceMethod) -> geographiclib_rs::Geodesic {
+    // Ellipsoid for the geodesic methods, WGS84 unless given
+    match method {
+        DistanceMethod::GeodesicEllipsoid(ellipsoid) => return ellipsoid.geodesic(),
+        _ => return Ellipsoid::WGS84.geodesic(),
+    }
+}
+
 #[test]
 fn test_find_closest_point() {
     use geo::{Coord, polygon};
@@ -163,3 +1111,638 @@ fn test_point_to_polygon_distance() {
     let test_dist = 100.3894682674663;
     assert_eq!(dist / 1000.0, test_dist);
 }
+
+#[test]
+fn test_find_closest_point_on_surface() {
+    use geo::{point, polygon};
+
+    // Diagonal edges at Scottish latitudes, where planar lon / lat distances distort
+    let polygon: Polygon<f64> = polygon![
+        (x: -4.0, y: 56.0),
+        (x: -3.0, y: 57.0),
+        (x: -5.0, y: 57.0),
+        (x: -4.0, y: 56.0),
+    ];
+    let point: Point = point!(x: -3.0, y: 56.0);
+
+    for method in [DistanceMethod::Haversine, DistanceMethod::Geodesic] {
+        let planar = find_closest_point(&point, &polygon);
+        let surface = find_closest_point_on_surface(&point, &polygon, &method);
+
+        let planar_dist = point_distance(&point, &planar, &method);
+        let surface_dist = point_distance(&point, &surface, &method);
+
+        // Densely sampled edge as a brute force reference
+        let brute_dist = (0..=10_000)
+            .map(|i| {
+                point_at_ratio(
+                    &point!(x: -4.0, y: 56.0),
+                    &point!(x: -3.0, y: 57.0),
+                    i as f64 / 10_000.0,
+                    &method,
+                )
+            })
+            .map(|p| point_distance(&point, &p, &method))
+            .fold(f64::MAX, f64::min);
+
+        assert!(surface_dist < planar_dist - 1_000.0);
+        assert!((surface_dist - brute_dist).abs() < 1.0);
+    }
+
+    // Contained points are returned unchanged
+    let inside: Point = point!(x: -4.0, y: 56.8);
+    assert_eq!(
+        find_closest_point_on_surface(&inside, &polygon, &DistanceMethod::Geodesic),
+        inside
+    );
+}
+
+#[test]
+fn test_distance_methods() {
+    let edinburgh: Point<f64> = Point::new(-3.2007650172960296, 55.95042325369335);
+    let glasgow: Point<f64> = Point::new(-4.251806, 55.864237);
+
+    let geodesic = point_distance(&edinburgh, &glasgow, &DistanceMethod::Geodesic);
+    let vincenty = point_distance(&edinburgh, &glasgow, &DistanceMethod::Vincenty);
+
+    // Vincenty & Karney agree on WGS84 to well under a millimetre
+    assert!((geodesic - vincenty).abs() < 0.001);
+
+    // Constant bearing routes are longer than the great circle over long distances
+    let new_york_city: Point<f64> = Point::new(-74.006f64, 40.7128f64);
+    let rhumb = point_distance(&new_york_city, &edinburgh, &DistanceMethod::Rhumb);
+    let haversine = point_distance(&new_york_city, &edinburgh, &DistanceMethod::Haversine);
+    assert!(rhumb > haversine + 100_000.0);
+
+    // Planar on projected co-ords is plain euclidean
+    let a = Point::new(325000.0, 673000.0);
+    let b = Point::new(328000.0, 677000.0);
+    assert_eq!(point_distance(&a, &b, &DistanceMethod::Planar), 5000.0);
+}
+
+#[test]
+fn test_point_distance_crs() {
+    use crs_definitions as crs_refs;
+    use geo::polygon;
+
+    let bng = crs_refs::EPSG_27700;
+    let a = Point::new(325000.0, 673000.0);
+    let b = Point::new(335000.0, 683000.0);
+
+    // Planar runs directly on BNG metres
+    let planar = point_distance_crs(&a, &b, &bng, &DistanceMethod::Planar).unwrap();
+    assert_eq!(planar, 200_000_000f64.sqrt());
+
+    // Geodesic auto-converts BNG to lon / lat first
+    let geodesic = point_distance_crs(&a, &b, &bng, &DistanceMethod::Geodesic).unwrap();
+    let manual = point_distance(
+        &coord::update_point_crs(a, &bng, &crs_refs::EPSG_4326),
+        &coord::update_point_crs(b, &bng, &crs_refs::EPSG_4326),
+        &DistanceMethod::Geodesic,
+    );
+    assert_eq!(geodesic, manual);
+
+    // BNG scale factor keeps grid & ground distance within 0.1%
+    assert!(100.0 * ((planar - geodesic) / geodesic).abs() < 0.1);
+
+    // Airy from the BNG CRS measures on OSGB36 lon / lat, not WGS84 co-ords
+    let airy = DistanceMethod::geodesic_from_crs(&bng);
+    let osgb36 = point_distance_crs(&a, &b, &bng, &airy).unwrap();
+    let manual = point_distance(
+        &coord::update_point_crs(a, &bng, &crs_refs::EPSG_4277),
+        &coord::update_point_crs(b, &bng, &crs_refs::EPSG_4277),
+        &airy,
+    );
+    assert_eq!(osgb36, manual);
+    assert!((osgb36 - geodesic).abs() < 1.0);
+
+    let haversine = DistanceMethod::haversine_from_crs(&bng);
+    let to_polygon = polygon![(x: 335000.0, y: 683000.0), (x: 336000.0, y: 683000.0), (x: 336000.0, y: 684000.0)];
+    assert_eq!(
+        point_polygon_distance_crs(&a, &to_polygon, &bng, &haversine).unwrap(),
+        point_distance(
+            &coord::update_point_crs(a, &bng, &crs_refs::EPSG_4277),
+            &coord::update_point_crs(b, &bng, &crs_refs::EPSG_4277),
+            &haversine,
+        )
+    );
+
+    // Planar on lon / lat is refused
+    let lon_lat = coord::update_point_crs(a, &bng, &crs_refs::EPSG_4326);
+    assert!(
+        point_distance_crs(
+            &lon_lat,
+            &lon_lat,
+            &crs_refs::EPSG_4326,
+            &DistanceMethod::Planar
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_ellipsoid_from_crs() {
+    use crs_definitions as crs_refs;
+
+    assert_eq!(
+        Ellipsoid::from_crs(&crs_refs::EPSG_27700),
+        Some(Ellipsoid::AIRY_1830)
+    );
+    assert_eq!(
+        Ellipsoid::from_crs(&crs_refs::EPSG_4326),
+        Some(Ellipsoid::WGS84)
+    );
+    assert_eq!(
+        Ellipsoid::from_crs(&crs_refs::EPSG_4258),
+        Some(Ellipsoid::GRS80)
+    );
+    assert_eq!(
+        DistanceMethod::geodesic_from_crs(&crs_refs::EPSG_27700),
+        DistanceMethod::GeodesicEllipsoid(Ellipsoid::AIRY_1830)
+    );
+}
+
+#[test]
+fn test_ellipsoid_distance() {
+    let edinburgh: Point<f64> = Point::new(-3.2007650172960296, 55.95042325369335);
+    let inverness: Point<f64> = Point::new(-4.224721, 57.477772);
+
+    // WGS84 via geographiclib matches geo's Geodesic
+    let geodesic = point_distance(&edinburgh, &inverness, &DistanceMethod::Geodesic);
+    let wgs84 = point_distance(
+        &edinburgh,
+        &inverness,
+        &DistanceMethod::GeodesicEllipsoid(Ellipsoid::WGS84),
+    );
+    assert!((geodesic - wgs84).abs() < 0.000_001);
+
+    // The smaller Airy 1830 ellipsoid gives a shorter distance
+    let airy = point_distance(
+        &edinburgh,
+        &inverness,
+        &DistanceMethod::GeodesicEllipsoid(Ellipsoid::AIRY_1830),
+    );
+    assert!(airy < wgs84 && wgs84 - airy < 50.0);
+
+    // Sphere radius is configurable
+    let haversine = point_distance(&edinburgh, &inverness, &DistanceMethod::Haversine);
+    assert_eq!(
+        point_distance(
+            &edinburgh,
+            &inverness,
+            &DistanceMethod::HaversineRadius(MEAN_EARTH_RADIUS)
+        ),
+        haversine
+    );
+    assert!(
+        point_distance(
+            &edinburgh,
+            &inverness,
+            &DistanceMethod::HaversineRadius(Ellipsoid::AIRY_1830.mean_radius())
+        ) < haversine
+    );
+
+    // Interpolation follows the chosen ellipsoid
+    let method = DistanceMethod::GeodesicEllipsoid(Ellipsoid::AIRY_1830);
+    let mid = point_at_ratio(&edinburgh, &inverness, 0.5, &method);
+    let first_half = point_distance(&edinburgh, &mid, &method);
+    assert!((first_half - airy / 2.0).abs() < 0.001);
+}
+
+#[test]
+fn test_bearings() {
+    let edinburgh: Point<f64> = Point::new(-3.2007650172960296, 55.95042325369335);
+    let inverness: Point<f64> = Point::new(-4.224721, 57.477772);
+
+    for method in [
+        DistanceMethod::Haversine,
+        DistanceMethod::Geodesic,
+        DistanceMethod::GeodesicEllipsoid(Ellipsoid::AIRY_1830),
+    ] {
+        let initial = initial_bearing(&edinburgh, &inverness, &method);
+        let last = final_bearing(&edinburgh, &inverness, &method);
+
+        // North north west, turning further west as the path heads north
+        assert!(initial > 330.0 && initial < 345.0);
+        assert!(last < initial && last > 325.0);
+    }
+
+    // Constant bearing for rhumb lines, grid bearing for planar
+    let rhumb = initial_bearing(&edinburgh, &inverness, &DistanceMethod::Rhumb);
+    assert_eq!(
+        rhumb,
+        final_bearing(&edinburgh, &inverness, &DistanceMethod::Rhumb)
+    );
+
+    let a = Point::new(325000.0, 673000.0);
+    let b = Point::new(326000.0, 672000.0);
+    assert_eq!(initial_bearing(&a, &b, &DistanceMethod::Planar), 135.0);
+}
+
+#[test]
+fn test_destination_point() {
+    let edinburgh: Point<f64> = Point::new(-3.2007650172960296, 55.95042325369335);
+    let inverness: Point<f64> = Point::new(-4.224721, 57.477772);
+
+    // Round trip from distance & bearing back to the target
+    for method in [
+        DistanceMethod::Haversine,
+        DistanceMethod::HaversineRadius(6_378_137.0),
+        DistanceMethod::Geodesic,
+        DistanceMethod::GeodesicEllipsoid(Ellipsoid::AIRY_1830),
+        DistanceMethod::Rhumb,
+    ] {
+        let bearing = initial_bearing(&edinburgh, &inverness, &method);
+        let distance = point_distance(&edinburgh, &inverness, &method);
+        let destination = destination_point(&edinburgh, bearing, distance, &method);
+
+        assert!(point_distance(&destination, &inverness, &method) < 0.01);
+    }
+
+    let a = Point::new(325000.0, 673000.0);
+    let destination = destination_point(&a, 90.0, 500.0, &DistanceMethod::Planar);
+    assert!((destination.x() - 325500.0).abs() < 1e-9);
+    assert!((destination.y() - 673000.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_path_linestring() {
+    let edinburgh: Point<f64> = Point::new(-3.2007650172960296, 55.95042325369335);
+    let inverness: Point<f64> = Point::new(-4.224721, 57.477772);
+    let method = DistanceMethod::Geodesic;
+
+    let distance = point_distance(&edinburgh, &inverness, &method);
+    let path = path_linestring(&edinburgh, &inverness, 10_000.0, &method);
+
+    // Ends preserved, segments no longer than requested & total length unchanged
+    assert_eq!(path.0.first(), Some(&edinburgh.0));
+    assert_eq!(path.0.last(), Some(&inverness.0));
+    assert_eq!(path.0.len(), (distance / 10_000.0).ceil() as usize + 1);
+
+    let lengths: Vec<f64> = path
+        .lines()
+        .map(|line| point_distance(&line.start.into(), &line.end.into(), &method))
+        .collect();
+    assert!(lengths.iter().all(|length| *length <= 10_000.0));
+    assert!((lengths.iter().sum::<f64>() - distance).abs() < 0.01);
+
+    assert_eq!(
+        intermediate_points(&edinburgh, &inverness, 3, &method).len(),
+        3
+    );
+}
+
+#[test]
+fn test_point_geometry_distance() {
+    use geo::{line_string, point, polygon};
+
+    let point: Point = point!(x: 325000.0, y: 673000.0);
+    let square: Polygon = polygon![
+        (x: 325100.0, y: 672900.0),
+        (x: 325300.0, y: 672900.0),
+        (x: 325300.0, y: 673100.0),
+        (x: 325100.0, y: 673100.0),
+        (x: 325100.0, y: 672900.0),
+    ];
+    let method = DistanceMethod::Planar;
+
+    let polygon_dist = point_geometry_distance(&point, &Geometry::Polygon(square.clone()), &method);
+    assert!((polygon_dist - 100.0).abs() < 0.000_001);
+
+    let line = line_string![(x: 324000.0, y: 673050.0), (x: 326000.0, y: 673050.0)];
+    let line_dist = point_geometry_distance(&point, &Geometry::LineString(line), &method);
+    assert!((line_dist - 50.0).abs() < 0.000_001);
+
+    // Contained points are zero distance
+    let inside: Point = point!(x: 325200.0, y: 673000.0);
+    assert_eq!(
+        point_geometry_distance(&inside, &Geometry::Polygon(square), &method),
+        0.0
+    );
+}
+
+#[test]
+fn test_geometry_distance() {
+    use geo::polygon;
+
+    let square: Polygon = polygon![
+        (x: 325000.0, y: 673000.0),
+        (x: 325100.0, y: 673000.0),
+        (x: 325100.0, y: 673100.0),
+        (x: 325000.0, y: 673100.0),
+        (x: 325000.0, y: 673000.0),
+    ];
+    let diamond: Polygon = polygon![
+        (x: 325300.0, y: 673050.0),
+        (x: 325400.0, y: 672950.0),
+        (x: 325500.0, y: 673050.0),
+        (x: 325400.0, y: 673150.0),
+        (x: 325300.0, y: 673050.0),
+    ];
+    let method = DistanceMethod::Planar;
+
+    let dist = geometry_distance(
+        &Geometry::Polygon(square.clone()),
+        &Geometry::Polygon(diamond.clone()),
+        &method,
+    );
+    assert!((dist - 200.0).abs() < 0.000_001);
+
+    // Overlapping geometries are zero distance
+    assert_eq!(
+        geometry_distance(
+            &Geometry::Polygon(square.clone()),
+            &Geometry::Polygon(square),
+            &method
+        ),
+        0.0
+    );
+}
+
+#[test]
+fn test_write_distance_matrix() {
+    use geo::point;
+
+    let sites = |prefix: &str, offset: f64| -> Vec<GeoData> {
+        (0..300)
+            .map(|idx| {
+                GeoData::from_geometry(
+                    &format!("{}_{}", prefix, idx),
+                    point!(x: 325000.0 + 100.0 * idx as f64, y: 673000.0 + offset),
+                )
+            })
+            .collect()
+    };
+    let mut from = sites("bw", 0.0);
+    let mut to = sites("sssi", 1_000.0);
+
+    // Features without a geometry are skipped on either side
+    from.push(GeoData::new(
+        "bw_none".to_string(),
+        None,
+        Default::default(),
+    ));
+    to.insert(
+        0,
+        GeoData::new("sssi_none".to_string(), None, Default::default()),
+    );
+
+    // Full matrix streams every pair
+    let mut buffer: Vec<u8> = vec![];
+    let rows =
+        write_distance_matrix(&from, &to, &DistanceMethod::Planar, None, &mut buffer).unwrap();
+    assert_eq!(rows, 300 * 300);
+
+    let mut reader = csv::Reader::from_reader(buffer.as_slice());
+    assert_eq!(
+        reader.headers().unwrap(),
+        vec!["from_uuid", "to_uuid", "distance"]
+    );
+    let first = reader.records().next().unwrap().unwrap();
+    assert_eq!(&first[0], "bw_0");
+    assert_eq!(&first[1], "sssi_0");
+    assert_eq!(first[2].parse::<f64>().unwrap(), 1_000.0);
+    let uuids = String::from_utf8(buffer.clone()).unwrap();
+    assert!(!uuids.contains("_none"));
+
+    // Thresholded matrix only holds pairs within range
+    let mut buffer: Vec<u8> = vec![];
+    let rows = write_distance_matrix(
+        &from,
+        &to,
+        &DistanceMethod::Planar,
+        Some(1_000.0),
+        &mut buffer,
+    )
+    .unwrap();
+    assert_eq!(rows, 300);
+
+    // Same pairs as filtering the full matrix
+    let mut reader = csv::Reader::from_reader(buffer.as_slice());
+    let pairs: Vec<(String, String)> = reader
+        .records()
+        .map(|record| {
+            let record = record.unwrap();
+            (record[0].to_string(), record[1].to_string())
+        })
+        .collect();
+    let expected: Vec<(String, String)> = (0..300)
+        .map(|idx| (format!("bw_{}", idx), format!("sssi_{}", idx)))
+        .collect();
+    assert_eq!(pairs, expected);
+}
+
+#[test]
+fn test_point_polygon_signed_distance() {
+    use geo::{point, polygon};
+
+    // 1km square with a 200m square hole in the middle
+    let polygon: Polygon<f64> = polygon!(
+        exterior: [
+            (x: 325000.0, y: 673000.0),
+            (x: 326000.0, y: 673000.0),
+            (x: 326000.0, y: 674000.0),
+            (x: 325000.0, y: 674000.0),
+            (x: 325000.0, y: 673000.0),
+        ],
+        interiors: [
+            [
+                (x: 325400.0, y: 673400.0),
+                (x: 325400.0, y: 673600.0),
+                (x: 325600.0, y: 673600.0),
+                (x: 325600.0, y: 673400.0),
+                (x: 325400.0, y: 673400.0),
+            ],
+        ],
+    );
+    let method = DistanceMethod::Planar;
+    let signed = |point: Point| point_polygon_signed_distance(&point, &polygon, &method);
+
+    // 50m inside & outside the exterior
+    assert!((signed(point!(x: 325050.0, y: 673200.0)) + 50.0).abs() < 0.000_001);
+    assert!((signed(point!(x: 324950.0, y: 673200.0)) - 50.0).abs() < 0.000_001);
+
+    // Inside near the hole measures to the hole edge, inside the hole is outside
+    assert!((signed(point!(x: 325350.0, y: 673500.0)) + 50.0).abs() < 0.000_001);
+    assert!((signed(point!(x: 325500.0, y: 673500.0)) - 100.0).abs() < 0.000_001);
+
+    // Multipolygon parts all count as boundary
+    let other = polygon![
+        (x: 327000.0, y: 673000.0),
+        (x: 328000.0, y: 673000.0),
+        (x: 328000.0, y: 674000.0),
+        (x: 327000.0, y: 674000.0),
+        (x: 327000.0, y: 673000.0),
+    ];
+    let multipolygon = MultiPolygon::new(vec![polygon.clone(), other]);
+    let between = point!(x: 326600.0, y: 673500.0);
+    assert!(
+        (point_multipolygon_signed_distance(&between, &multipolygon, &method) - 400.0).abs()
+            < 0.000_001
+    );
+}
+
+#[test]
+fn test_closest_point_on_edges() {
+    use geo::{point, polygon};
+
+    let square: Polygon = polygon![
+        (x: 325000.0, y: 673000.0),
+        (x: 326000.0, y: 673000.0),
+        (x: 326000.0, y: 674000.0),
+        (x: 325000.0, y: 674000.0),
+        (x: 325000.0, y: 673000.0),
+    ];
+
+    // Points inside still snap to the boundary
+    let inside: Point = point!(x: 325030.0, y: 673500.0);
+    let (closest, dist) =
+        closest_point_on_edges(&inside, &Geometry::Polygon(square), &DistanceMethod::Planar)
+            .unwrap();
+    assert!((closest.x() - 325000.0).abs() < 0.000_001);
+    assert!((closest.y() - 673500.0).abs() < 0.000_001);
+    assert!((dist - 30.0).abs() < 0.000_001);
+
+    assert!(
+        closest_point_on_edges(&inside, &Geometry::Point(inside), &DistanceMethod::Planar)
+            .is_none()
+    );
+}
+
+#[test]
+fn test_obstacle_path() {
+    use crate::measure;
+    use geo::{point, polygon};
+
+    // Headland between two bays, 1km wide reaching 2km north
+    let headland: Polygon = polygon![
+        (x: 320000.0, y: 670000.0),
+        (x: 321000.0, y: 670000.0),
+        (x: 321000.0, y: 672000.0),
+        (x: 320000.0, y: 672000.0),
+        (x: 320000.0, y: 670000.0),
+    ];
+    let land = MultiPolygon::new(vec![headland]);
+    let west: Point = point!(x: 319000.0, y: 671000.0);
+    let east: Point = point!(x: 322000.0, y: 671000.0);
+
+    // Around the north tip : two 1km x 1km diagonals & 1km across the top
+    let (path, dist) = obstacle_path(&west, &east, &land, &DistanceMethod::Planar).unwrap();
+    let expected = 2.0 * 2.0_f64.sqrt() * 1000.0 + 1000.0;
+    assert!((dist - expected).abs() < 0.001);
+    assert_eq!(path.0.len(), 4);
+    assert_eq!(path.0.first(), Some(&west.0));
+    assert_eq!(path.0.last(), Some(&east.0));
+    assert!((measure::linestring_length(&path, &DistanceMethod::Planar) - dist).abs() < 0.001);
+
+    // Clear water is the straight line
+    let north: Point = point!(x: 322000.0, y: 673000.0);
+    let (path, dist) = obstacle_path(&east, &north, &land, &DistanceMethod::Planar).unwrap();
+    assert_eq!(path.0.len(), 2);
+    assert!((dist - 2000.0).abs() < 0.001);
+
+    // Starting on land has no over-water path
+    let inland: Point = point!(x: 320500.0, y: 671000.0);
+    assert!(obstacle_path(&inland, &east, &land, &DistanceMethod::Planar).is_none());
+}
+
+#[test]
+fn test_obstacle_path_coastline() {
+    use geo::point;
+
+    // Island with a jagged 4000 vertex coastline, 10km out with 100m inlets between headlands
+    let (centre_x, centre_y) = (330000.0, 680000.0);
+    let coastline: Vec<Coord> = (0..4000)
+        .map(|idx| {
+            let angle = idx as f64 * std::f64::consts::TAU / 4000.0;
+            let radius = match idx % 2 {
+                0 => 10000.0,
+                _ => 9900.0,
+            };
+            Coord {
+                x: centre_x + radius * angle.cos(),
+                y: centre_y + radius * angle.sin(),
+            }
+        })
+        .collect();
+    let island = Polygon::new(LineString::new(coastline), vec![]);
+    let land = MultiPolygon::new(vec![island]);
+    let west: Point = point!(x: centre_x - 15000.0, y: centre_y);
+    let east: Point = point!(x: centre_x + 15000.0, y: centre_y);
+
+    // Between the tangent & arc route around circles of the inlet & headland radii
+    let (path, dist) = obstacle_path(&west, &east, &land, &DistanceMethod::Planar).unwrap();
+    let around = |radius: f64| {
+        let tangent = (15000.0_f64.powi(2) - radius.powi(2)).sqrt();
+        let arc = std::f64::consts::PI - 2.0 * (radius / 15000.0).acos();
+        2.0 * tangent + radius * arc
+    };
+    assert!(dist > around(9900.0) && dist < around(10000.0));
+
+    // Every leg stays off the island
+    for leg in path.lines() {
+        let mid = Point::new(
+            (leg.start.x + leg.end.x) / 2.0,
+            (leg.start.y + leg.end.y) / 2.0,
+        );
+        assert!(!land.contains(&mid));
+    }
+}
+
+#[test]
+fn test_path_crossings() {
+    use crate::coord;
+    use geo::{line_string, polygon};
+
+    // Path crosses two of the sites & passes by the others
+    let site = |uuid: &str, x: f64, y: f64, size: f64| {
+        GeoData::from_geometry(
+            uuid,
+            polygon![
+                (x: x, y: y),
+                (x: x + size, y: y),
+                (x: x + size, y: y + size),
+                (x: x, y: y + size),
+                (x: x, y: y),
+            ],
+        )
+    };
+    let sites = vec![
+        site("spa", 321000.0, 669500.0, 1000.0),
+        site("sssi", 323000.0, 669000.0, 500.0),
+        site("ramsar", 325000.0, 669000.0, 1000.0),
+        site("sac", 327000.0, 669000.0, 500.0),
+    ];
+    let path: LineString = line_string![
+        (x: 320000.0, y: 670000.0),
+        (x: 325000.0, y: 670000.0),
+        (x: 325500.0, y: 669500.0),
+    ];
+
+    let crossings = path_crossings(&path, &sites, &DistanceMethod::Planar);
+    let uuids: Vec<&str> = crossings.iter().map(|row| row.uuid.as_str()).collect();
+    assert_eq!(uuids, vec!["spa", "ramsar"]);
+    assert!((crossings[0].length - 1000.0).abs() < 0.001);
+    assert!((crossings[1].length - 500.0 * 2.0_f64.sqrt()).abs() < 0.001);
+
+    // Same crossing on a densified lon / lat path, within the BNG scale factor
+    let to_lon_lat = |coords: &LineString| -> LineString {
+        coords
+            .coords()
+            .map(|coord| {
+                coord::update_point_crs(Point(*coord), &crs_refs::EPSG_27700, &crs_refs::EPSG_4326)
+            })
+            .collect()
+    };
+    let sites_tf: Vec<GeoData> = sites
+        .iter()
+        .map(|row| coord::update_feature_crs(row, &crs_refs::EPSG_27700, &crs_refs::EPSG_4326))
+        .collect();
+    let start = to_lon_lat(&path).0[0];
+    let end = to_lon_lat(&path).0[1];
+    let path_tf = path_linestring(&Point(start), &Point(end), 10.0, &DistanceMethod::Geodesic);
+
+    let crossings = path_crossings(&path_tf, &sites_tf, &DistanceMethod::Geodesic);
+    assert_eq!(crossings.len(), 1);
+    assert!(100.0 * ((crossings[0].length - 1000.0) / 1000.0).abs() < 0.1);
+}
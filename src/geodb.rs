@@ -1,28 +1,654 @@
 // Geodatabase handler
+use crate::buffer;
+use crate::coord;
+use crate::dist::{self, DistanceMethod};
+use crate::measure;
 use crate::utils::unzip;
-use crs_definitions;
-use geo::{Geometry, MultiPolygon, Point, Polygon};
+use geo::orient::{Direction, Orient};
+use geo::{
+    BoundingRect, Coord, CoordsIter, Geometry, Intersects, MultiPolygon, Point, Polygon, Rect,
+};
 use geozero::wkb::{FromWkb, WkbDialect};
 use reqwest::blocking::get;
-use rusqlite::Connection;
-use std::fs::{File, create_dir, read_dir, remove_file};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, OpenFlags, Row, Rows, Statement, params_from_iter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
 use std::io::{Cursor, copy};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GeoData {
     pub uuid: String,
-    pub point: Option<Point>,
-    pub polygon: Option<Polygon>,
-    pub multipolygon: Option<MultiPolygon>,
+    // None when the source geometry is null, empty or undecodable
+    pub geometry: Option<Geometry>,
+    pub properties: Properties,
+}
+
+// Attribute columns by name, ordered for stable output
+pub type Properties = BTreeMap<String, PropertyValue>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PropertyValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+    // ISO 8601 text from DATE / DATETIME columns
+    Date(String),
+}
+
+impl PropertyValue {
+    fn from_column(value: ValueRef, decl_type: Option<&str>) -> PropertyValue {
+        // GeoPackage dates are stored as text, the declared column type marks them out
+        let is_date = decl_type.is_some_and(|decl_type| {
+            decl_type.eq_ignore_ascii_case("DATE") || decl_type.eq_ignore_ascii_case("DATETIME")
+        });
+
+        match value {
+            ValueRef::Null => return PropertyValue::Null,
+            ValueRef::Integer(int) => return PropertyValue::Integer(int),
+            ValueRef::Real(real) => return PropertyValue::Real(real),
+            ValueRef::Text(text) if is_date => {
+                return PropertyValue::Date(String::from_utf8_lossy(text).to_string());
+            }
+            ValueRef::Text(text) => {
+                return PropertyValue::Text(String::from_utf8_lossy(text).to_string());
+            }
+            ValueRef::Blob(blob) => return PropertyValue::Blob(blob.to_vec()),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        // SQLite value for writing / query parameters, dates as ISO 8601 text
+        match self {
+            PropertyValue::Null => return Value::Null,
+            PropertyValue::Integer(int) => return Value::Integer(*int),
+            PropertyValue::Real(real) => return Value::Real(*real),
+            PropertyValue::Text(text) | PropertyValue::Date(text) => {
+                return Value::Text(text.clone());
+            }
+            PropertyValue::Blob(blob) => return Value::Blob(blob.clone()),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        return matches!(self, PropertyValue::Null);
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            PropertyValue::Integer(int) => return Some(*int),
+            _ => return None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        // Integers widen to floats
+        match self {
+            PropertyValue::Integer(int) => return Some(*int as f64),
+            PropertyValue::Real(real) => return Some(*real),
+            _ => return None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Text(text) | PropertyValue::Date(text) => return Some(text),
+            _ => return None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            PropertyValue::Blob(blob) => return Some(blob),
+            _ => return None,
+        }
+    }
+
+    pub fn as_date(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Date(date) => return Some(date),
+            _ => return None,
+        }
+    }
+}
+
+impl GeoData {
+    pub fn new(uuid: String, geometry: Option<Geometry>, properties: Properties) -> GeoData {
+        return GeoData {
+            uuid,
+            geometry,
+            properties,
+        };
+    }
+
+    pub fn from_geometry(uuid: &str, geometry: impl Into<Geometry>) -> GeoData {
+        // Feature without properties, from a point / polygon / line...
+        return GeoData::new(uuid.to_string(), Some(geometry.into()), Properties::new());
+    }
+
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        return self.properties.get(name);
+    }
+
+    pub fn point(&self) -> Option<&Point> {
+        match &self.geometry {
+            Some(Geometry::Point(point)) => return Some(point),
+            _ => return None,
+        }
+    }
+
+    pub fn polygon(&self) -> Option<&Polygon> {
+        match &self.geometry {
+            Some(Geometry::Polygon(polygon)) => return Some(polygon),
+            _ => return None,
+        }
+    }
+
+    pub fn multipolygon(&self) -> Option<&MultiPolygon> {
+        match &self.geometry {
+            Some(Geometry::MultiPolygon(multipolygon)) => return Some(multipolygon),
+            _ => return None,
+        }
+    }
+
+    pub fn to_multipolygon(&self) -> Option<MultiPolygon> {
+        // Any areal geometry as a multipolygon
+        match &self.geometry {
+            Some(Geometry::Polygon(polygon)) => {
+                return Some(MultiPolygon::new(vec![polygon.clone()]));
+            }
+            Some(Geometry::MultiPolygon(multipolygon)) => return Some(multipolygon.clone()),
+            Some(Geometry::Rect(rect)) => return Some(MultiPolygon::new(vec![rect.to_polygon()])),
+            Some(Geometry::Triangle(triangle)) => {
+                return Some(MultiPolygon::new(vec![triangle.to_polygon()]));
+            }
+            _ => return None,
+        }
+    }
+
+    pub fn normalise_polygons(&mut self) {
+        // Areal geometries become multipolygons, exteriors counter-clockwise & holes clockwise
+        if let Some(multipolygon) = self.to_multipolygon() {
+            self.geometry = Some(Geometry::MultiPolygon(
+                multipolygon.orient(Direction::Default),
+            ));
+        }
+    }
+
+    pub fn area(&self, method: &DistanceMethod) -> f64 {
+        // Area (m2) net of holes, non areal geometries have none
+        match &self.geometry {
+            Some(Geometry::Polygon(polygon)) => return measure::polygon_area(polygon, method),
+            Some(Geometry::MultiPolygon(multipolygon)) => {
+                return measure::multipolygon_area(multipolygon, method);
+            }
+            _ => (),
+        }
+        match self.to_multipolygon() {
+            Some(multipolygon) => return measure::multipolygon_area(&multipolygon, method),
+            None => return 0.0,
+        }
+    }
+
+    pub fn perimeter(&self, method: &DistanceMethod) -> f64 {
+        // Boundary length (m) including holes, non areal geometries have none
+        match &self.geometry {
+            Some(Geometry::Polygon(polygon)) => return measure::polygon_perimeter(polygon, method),
+            Some(Geometry::MultiPolygon(multipolygon)) => {
+                return measure::multipolygon_perimeter(multipolygon, method);
+            }
+            _ => (),
+        }
+        match self.to_multipolygon() {
+            Some(multipolygon) => return measure::multipolygon_perimeter(&multipolygon, method),
+            None => return 0.0,
+        }
+    }
+
+    pub fn buffer(&self, distance: f64, segments: usize, method: &DistanceMethod) -> MultiPolygon {
+        // Buffer (m) of whichever geometry the feature holds
+        match &self.geometry {
+            Some(geometry) => return buffer::buffer_geometry(geometry, distance, segments, method),
+            None => return MultiPolygon::new(vec![]),
+        }
+    }
+
+    pub fn bounding_rect(&self) -> Option<Rect> {
+        return self.geometry.as_ref()?.bounding_rect();
+    }
+
+    pub fn distance_to_geometry(&self, geometry: &Geometry, method: &DistanceMethod) -> f64 {
+        // Exact distance (m) between the nearest parts of the feature & geometry
+        match &self.geometry {
+            Some(own) => return dist::geometry_distance(geometry, own, method),
+            None => return f64::INFINITY,
+        }
+    }
+
+    pub fn signed_distance_to_point(&self, point: &Point, method: &DistanceMethod) -> f64 {
+        // Distance (m) to the feature boundary, negative inside - non areal geometries have no inside
+        match &self.geometry {
+            Some(Geometry::Polygon(polygon)) => {
+                return dist::point_polygon_signed_distance(point, polygon, method);
+            }
+            Some(Geometry::MultiPolygon(multipolygon)) => {
+                return dist::point_multipolygon_signed_distance(point, multipolygon, method);
+            }
+            _ => (),
+        }
+        match self.to_multipolygon() {
+            Some(multipolygon) => {
+                return dist::point_multipolygon_signed_distance(point, &multipolygon, method);
+            }
+            None => return self.distance_to_point(point, method),
+        }
+    }
+
+    pub fn distance_to_point(&self, point: &Point, method: &DistanceMethod) -> f64 {
+        // Exact distance (m) to the nearest part of the feature, zero when contained
+        match &self.geometry {
+            Some(geometry) => return dist::point_geometry_distance(point, geometry, method),
+            None => return f64::INFINITY,
+        }
+    }
+}
+
+fn list_layers(conn: &Connection) -> Vec<LayerInfo> {
+    let mut engine = conn
+        .prepare(
+            "SELECT contents.table_name, contents.data_type, contents.identifier,
+                geom.column_name, geom.geometry_type_name, contents.srs_id,
+                contents.min_x, contents.min_y, contents.max_x, contents.max_y
+            FROM gpkg_contents AS contents
+            LEFT JOIN gpkg_geometry_columns AS geom ON contents.table_name = geom.table_name
+            ORDER BY contents.table_name",
+        )
+        .expect("failed to read gpkg_contents");
+
+    let layers = engine
+        .query_map([], |row| {
+            let bbox = match (row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?) {
+                (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) => Some(Rect::new(
+                    Coord { x: min_x, y: min_y },
+                    Coord { x: max_x, y: max_y },
+                )),
+                _ => None,
+            };
+            Ok(LayerInfo {
+                table: row.get(0)?,
+                data_type: row.get(1)?,
+                identifier: row.get(2)?,
+                geometry_column: row.get(3)?,
+                geometry_type: row.get(4)?,
+                srs_id: row.get(5)?,
+                bbox,
+                feature_count: 0,
+                columns: vec![],
+            })
+        })
+        .expect("failed to query gpkg_contents")
+        .collect::<Result<Vec<LayerInfo>, _>>()
+        .expect("failed to read layer");
+
+    // Row counts & column schema from the layer tables themselves
+    return layers
+        .into_iter()
+        .map(|mut layer| {
+            layer.feature_count = conn
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {}", quote_ident(&layer.table)),
+                    [],
+                    |row| row.get(0),
+                )
+                .expect("failed to count features");
+            layer.columns = table_columns(conn, &layer.table);
+            if layer.bbox.is_none() {
+                layer.bbox = rtree_extent(conn, &layer);
+            }
+            layer
+        })
+        .collect();
+}
+
+fn rtree_extent(conn: &Connection, layer: &LayerInfo) -> Option<Rect> {
+    // gpkg_contents bbox is optional, the layer's RTree index holds the same extent
+    let rtree = format!("rtree_{}_{}", layer.table, layer.geometry_column.as_ref()?);
+    let extent = conn.query_row(
+        &format!(
+            "SELECT MIN(minx), MIN(miny), MAX(maxx), MAX(maxy) FROM {}",
+            quote_ident(&rtree)
+        ),
+        [],
+        |row| {
+            Ok((
+                row.get::<_, Option<f64>>(0)?,
+                row.get::<_, Option<f64>>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        },
+    );
+
+    match extent {
+        Ok((Some(min_x), Some(min_y), Some(max_x), Some(max_y))) => {
+            return Some(Rect::new(
+                Coord { x: min_x, y: min_y },
+                Coord { x: max_x, y: max_y },
+            ));
+        }
+        _ => return None,
+    }
+}
+
+fn rtree_table(conn: &Connection, table: &str) -> Option<String> {
+    // gpkg_rtree_index virtual table for the layer's geometry column, if one was built
+    let geometry_column: String = conn
+        .query_row(
+            "SELECT column_name FROM gpkg_geometry_columns WHERE table_name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .ok()?;
+    let rtree = format!("rtree_{}_{}", table, geometry_column);
+    return conn
+        .query_row(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [&rtree],
+            |row| row.get(0),
+        )
+        .ok();
+}
+
+fn filter_conditions(
+    conn: &Connection,
+    table: &str,
+    filter: &FeatureFilter,
+) -> Result<(Vec<String>, Vec<Value>, bool), String> {
+    // WHERE conditions & their parameters, plus whether the bbox is left to check against feature envelopes
+    let columns = table_columns(conn, table);
+    let mut conditions = vec![];
+    let mut values = vec![];
+
+    // Column names can't be bound, so only names in the table schema are accepted
+    for attribute in &filter.attributes {
+        let column = match columns
+            .iter()
+            .find(|column| column.name.eq_ignore_ascii_case(&attribute.column))
+        {
+            Some(column) => column,
+            None => return Err(format!("no {} column in {}", attribute.column, table)),
+        };
+        let name = quote_ident(&column.name);
+
+        let (condition, operands) = match &attribute.condition {
+            Condition::Equal(value) => (format!("{} = ?", name), vec![value.to_value()]),
+            Condition::NotEqual(value) => (format!("{} != ?", name), vec![value.to_value()]),
+            Condition::Less(value) => (format!("{} < ?", name), vec![value.to_value()]),
+            Condition::LessEqual(value) => (format!("{} <= ?", name), vec![value.to_value()]),
+            Condition::Greater(value) => (format!("{} > ?", name), vec![value.to_value()]),
+            Condition::GreaterEqual(value) => (format!("{} >= ?", name), vec![value.to_value()]),
+            Condition::Like(pattern) => (
+                format!("{} LIKE ?", name),
+                vec![Value::Text(pattern.clone())],
+            ),
+            Condition::In(options) => (
+                format!("{} IN ({})", name, vec!["?"; options.len()].join(", ")),
+                options.iter().map(|value| value.to_value()).collect(),
+            ),
+            Condition::IsNull => (format!("{} IS NULL", name), vec![]),
+            Condition::NotNull => (format!("{} IS NOT NULL", name), vec![]),
+        };
+        conditions.push(condition);
+        values.extend(operands);
+    }
+
+    // Bbox via the RTree index when present, keyed on the feature id
+    let mut envelope_check = false;
+    if let Some(bbox) = filter.bbox {
+        match (rtree_table(conn, table), feature_id_column(&columns)) {
+            (Some(rtree), Some(id)) => {
+                conditions.push(format!(
+                    "{} IN (SELECT id FROM {} WHERE minx <= ? AND maxx >= ? AND miny <= ? AND maxy >= ?)",
+                    quote_ident(&id),
+                    quote_ident(&rtree)
+                ));
+                values.extend([
+                    Value::Real(bbox.max().x),
+                    Value::Real(bbox.min().x),
+                    Value::Real(bbox.max().y),
+                    Value::Real(bbox.min().y),
+                ]);
+            }
+            _ => envelope_check = true,
+        }
+    }
+
+    return Ok((conditions, values, envelope_check));
+}
+
+pub fn quote_ident(name: &str) -> String {
+    // Table / column names can't be bound, so are quoted with any embedded quotes doubled
+    return format!("\"{}\"", name.replace('"', "\"\""));
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Vec<ColumnInfo> {
+    let mut engine = conn
+        .prepare(&format!("PRAGMA table_info({})", quote_ident(table)))
+        .expect("failed to read table info");
+
+    return engine
+        .query_map([], |row| {
+            Ok(ColumnInfo {
+                name: row.get(1)?,
+                data_type: row.get(2)?,
+                not_null: row.get(3)?,
+                primary_key: row.get::<_, i32>(5)? > 0,
+            })
+        })
+        .expect("failed to query table info")
+        .collect::<Result<Vec<ColumnInfo>, _>>()
+        .expect("failed to read column");
+}
+
+fn decode_geometry(value: ValueRef) -> Result<Geometry, GeometryError> {
+    // GeoPackage WKB : header (flags in byte 3) then standard WKB
+    let bytes = match value {
+        ValueRef::Null => return Err(GeometryError::Null),
+        ValueRef::Blob(bytes) => bytes,
+        _ => return Err(GeometryError::Undecodable("not a blob".to_string())),
+    };
+    if bytes.len() > 3 && bytes[0..2] == *b"GP" && bytes[3] & 0b0001_0000 != 0 {
+        return Err(GeometryError::Empty);
+    }
+
+    let mut bytes_cursor = Cursor::new(bytes);
+    match Geometry::from_wkb(&mut bytes_cursor, WkbDialect::Geopackage) {
+        Ok(Geometry::Point(point)) if point.x().is_nan() || point.y().is_nan() => {
+            return Err(GeometryError::Empty);
+        }
+        Ok(geometry) if geometry.coords_count() == 0 => return Err(GeometryError::Empty),
+        Ok(geometry) => return Ok(geometry),
+        Err(error) => return Err(GeometryError::Undecodable(error.to_string())),
+    }
+}
+
+fn header_envelope(bytes: &[u8]) -> Option<Rect> {
+    // xy envelope from the GeoPackage header, if written : flags bits 1-3 indicator, bit 0 byte order
+    if bytes.len() < 8 || bytes[0..2] != *b"GP" || (bytes[3] >> 1) & 0b111 == 0 {
+        return None;
+    }
+    let little_endian = bytes[3] & 0b0000_0001 != 0;
+    let mut values = [0.0; 4];
+    for (idx, value) in values.iter_mut().enumerate() {
+        let start = 8 + idx * 8;
+        let raw: [u8; 8] = bytes.get(start..start + 8)?.try_into().ok()?;
+        *value = match little_endian {
+            true => f64::from_le_bytes(raw),
+            false => f64::from_be_bytes(raw),
+        };
+    }
+
+    // Envelope order is minx, maxx, miny, maxy
+    return Some(Rect::new(
+        Coord {
+            x: values[0],
+            y: values[2],
+        },
+        Coord {
+            x: values[1],
+            y: values[3],
+        },
+    ));
+}
+
+fn geometry_envelope(value: ValueRef) -> Option<Rect> {
+    // Header envelope, else the decoded geometry's bounds (points are written without one)
+    if let ValueRef::Blob(bytes) = value
+        && let Some(rect) = header_envelope(bytes)
+    {
+        return Some(rect);
+    }
+    return decode_geometry(value).ok()?.bounding_rect();
+}
+
+fn uuid_value(value: ValueRef) -> String {
+    // Ids can be any column type, blobs (e.g. binary GUIDs) as hex
+    match value {
+        ValueRef::Text(text) => return String::from_utf8_lossy(text).to_string(),
+        ValueRef::Integer(int) => return int.to_string(),
+        ValueRef::Real(real) => return real.to_string(),
+        ValueRef::Null => return String::new(),
+        ValueRef::Blob(blob) => return blob.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Equal(PropertyValue),
+    NotEqual(PropertyValue),
+    Less(PropertyValue),
+    LessEqual(PropertyValue),
+    Greater(PropertyValue),
+    GreaterEqual(PropertyValue),
+    Like(String),
+    In(Vec<PropertyValue>),
+    IsNull,
+    NotNull,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeFilter {
+    pub column: String,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureFilter {
+    // Features whose envelope intersects the bbox, in the layer CRS
+    pub bbox: Option<Rect>,
+    // Combined with AND, values are bound as query parameters
+    pub attributes: Vec<AttributeFilter>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GeoSource {
+    // Web hosted gpkg, or zip archive holding the gpkg
+    Url {
+        url: String,
+        zipfile: Option<String>,
+    },
+    // Zip archive on disk holding the gpkg
+    Zip(String),
+    // GeoPackage on disk, read in place
+    Gpkg(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    Null,
+    Empty,
+    Undecodable(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometryIssue {
+    pub uuid: String,
+    pub error: GeometryError,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GeoLayer {
+    // None when the gpkg has no CRS metadata for the layer
+    pub srs_id: Option<i32>,
+    pub crs: Option<crs_definitions::Def>,
+    // Layer & expected EPSG codes when the layer isn't in the GeoDB crs
+    pub crs_mismatch: Option<(u16, u16)>,
+    pub data: Vec<GeoData>,
+    // Features kept without a geometry & why
+    pub issues: Vec<GeometryIssue>,
+}
+
+impl GeoLayer {
+    pub fn check_crs(&self, crs: &crs_definitions::Def) -> Result<(), String> {
+        // Error when the layer isn't in the expected CRS
+        match &self.crs {
+            Some(layer_crs) if layer_crs.code == crs.code => return Ok(()),
+            Some(layer_crs) => {
+                return Err(format!(
+                    "layer CRS is EPSG:{}, expected EPSG:{}",
+                    layer_crs.code, crs.code
+                ));
+            }
+            None => match self.srs_id {
+                Some(srs_id) => return Err(format!("layer CRS (srs_id {}) is unknown", srs_id)),
+                None => return Err("layer has no CRS metadata".to_string()),
+            },
+        }
+    }
+
+    pub fn normalise_polygons(&mut self) {
+        for row in &mut self.data {
+            row.normalise_polygons();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    // Declared SQLite type (e.g. TEXT(50), MULTIPOLYGON)
+    pub data_type: String,
+    pub not_null: bool,
+    pub primary_key: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerInfo {
+    pub table: String,
+    // features, attributes or tiles
+    pub data_type: String,
+    pub identifier: Option<String>,
+    pub geometry_column: Option<String>,
+    pub geometry_type: Option<String>,
+    pub srs_id: Option<i32>,
+    pub bbox: Option<Rect>,
+    pub feature_count: usize,
+    pub columns: Vec<ColumnInfo>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct GeoDB {
-    pub url: String,
-    pub zipfile: Option<String>,
+    pub source: GeoSource,
     pub db: String,
-    pub table: String,
-    pub crs: crs_definitions::Def,
+    // Optional when the gpkg holds a single layer
+    pub table: Option<String>,
+    pub crs: Option<crs_definitions::Def>,
+    pub uuid_col: Option<String>,
     pub uuid_col_idx: i32,
     pub geometry_col_idx: i32,
 }
@@ -30,12 +656,15 @@ pub struct GeoDB {
 impl GeoDB {
     pub fn example_points_db() -> GeoDB {
         return GeoDB {
-            url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip"
-                .to_string(),
-            zipfile: Some("SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string()),
+            source: GeoSource::Url {
+                url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip"
+                    .to_string(),
+                zipfile: Some("SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string()),
+            },
             db: "SEPA_BATHING_WATER_POINTS_BNG.gpkg".to_string(),
-            table: "SEPA_BATHING_WATER_POINTS_BNG".to_string(),
-            crs: crs_definitions::EPSG_27700,
+            table: Some("SEPA_BATHING_WATER_POINTS_BNG".to_string()),
+            crs: Some(crs_definitions::EPSG_27700),
+            uuid_col: Some("bw_url".to_string()),
             geometry_col_idx: 1,
             uuid_col_idx: 8,
         };
@@ -43,116 +672,491 @@ impl GeoDB {
 
     pub fn example_polygons_db() -> GeoDB {
         return GeoDB {
-            url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip"
-                .to_string(),
-            zipfile: Some("SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip".to_string()),
+            source: GeoSource::Url {
+                url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip"
+                    .to_string(),
+                zipfile: Some("SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip".to_string()),
+            },
             db: "SEPA_BATHING_WATER_POLYGONS_BNG.gpkg".to_string(),
-            table: "SEPA_BATHING_WATER_POLYGONS_BNG".to_string(),
-            crs: crs_definitions::EPSG_27700,
+            table: Some("SEPA_BATHING_WATER_POLYGONS_BNG".to_string()),
+            crs: Some(crs_definitions::EPSG_27700),
+            uuid_col: Some("bw_url".to_string()),
             geometry_col_idx: 1,
             uuid_col_idx: 8,
         };
     }
 
-    fn get_gdb(&self) {
-        // Setup working dir if req'd
-        match read_dir("tmp") {
-            Ok(_) => (),
-            Err(_) => create_dir("tmp").expect("failed to create working dir"),
+    pub fn example_polygons_local_db() -> GeoDB {
+        // Bundled copy of the polygons dataset
+        return GeoDB {
+            source: GeoSource::Zip("data/SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip".to_string()),
+            ..GeoDB::example_polygons_db()
+        };
+    }
+
+    fn get_gdb(&self, work_dir: &Path) -> PathBuf {
+        // Path to the gpkg, fetching / unzipping into work_dir if req'd
+        let dest = work_dir
+            .to_str()
+            .expect("failed to convert working dir to str");
+
+        match &self.source {
+            GeoSource::Gpkg(path) => return PathBuf::from(path),
+            GeoSource::Zip(path) => {
+                unzip(path, dest);
+                return work_dir.join(&self.db);
+            }
+            GeoSource::Url { url, zipfile } => {
+                // Extract from url
+                let mut response = get(url).expect("failed to get file from url");
+
+                // Copy to working dir
+                let file_name = zipfile.as_ref().unwrap_or(&self.db);
+                let file_path = work_dir.join(file_name);
+                let mut file = File::create(&file_path).expect("failed to create db_path");
+                copy(&mut response, &mut file).expect("failed to copy content to db_path");
+
+                // Unzip archive
+                if zipfile.is_some() {
+                    unzip(
+                        file_path
+                            .to_str()
+                            .expect("failed to convert zip path to str"),
+                        dest,
+                    );
+                }
+                return work_dir.join(&self.db);
+            }
+        }
+    }
+
+    fn connect(&self, work_dir: &Path) -> Connection {
+        // Read only, so a mistyped gpkg path fails rather than creating an empty DB
+        let db_path = self.get_gdb(work_dir);
+        return Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .unwrap_or_else(|error| panic!("failed to connect to DB {:?} : {}", db_path, error));
+    }
+
+    pub fn layers(&self) -> Vec<LayerInfo> {
+        // Every layer listed in gpkg_contents
+        let work_dir = TempDir::new().expect("failed to create working dir");
+        let conn = self.connect(work_dir.path());
+        return list_layers(&conn);
+    }
+
+    fn table_name(&self, conn: &Connection) -> Result<String, String> {
+        // Set table, or the only features layer in the gpkg (attribute & tile tables aside)
+        if let Some(table) = &self.table {
+            return Ok(table.clone());
+        }
+        let mut engine = conn
+            .prepare("SELECT table_name FROM gpkg_contents WHERE data_type = 'features'")
+            .expect("failed to read gpkg_contents");
+        let tables = engine
+            .query_map([], |row| row.get::<_, String>(0))
+            .expect("failed to query gpkg_contents")
+            .collect::<Result<Vec<String>, _>>()
+            .expect("failed to read layer");
+
+        match tables.as_slice() {
+            [table] => return Ok(table.clone()),
+            _ => {
+                return Err(format!(
+                    "{} holds {} feature layers, a table name is needed",
+                    self.db,
+                    tables.len()
+                ));
+            }
+        }
+    }
+
+    fn column_indices(
+        &self,
+        conn: &Connection,
+        engine: &Statement,
+        table: &str,
+    ) -> Result<(usize, usize), String> {
+        // Geometry column from gpkg_geometry_columns & id column by name, else the set indices
+        let geometry_idx = conn
+            .query_row(
+                "SELECT column_name FROM gpkg_geometry_columns WHERE table_name = ?1",
+                [table],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|column| engine.column_index(&column).ok())
+            .unwrap_or(self.geometry_col_idx as usize);
+
+        let uuid_idx = match &self.uuid_col {
+            Some(column) => match engine.column_index(column) {
+                Ok(idx) => idx,
+                Err(_) => return Err(format!("no {} column in {}", column, table)),
+            },
+            None => self.uuid_col_idx as usize,
+        };
+
+        // Set indices aren't checked against the schema until here
+        let column_count = engine.column_count();
+        for idx in [geometry_idx, uuid_idx] {
+            if idx >= column_count {
+                return Err(format!("no column {} in {}", idx, table));
+            }
         }
 
-        // Extract from url
-        let mut response = get(&self.url).expect("failed to get file from url");
+        return Ok((geometry_idx, uuid_idx));
+    }
 
-        // Copy to working dir
-        if self.zipfile.is_some() {
-            let zip_path = format!("tmp/{}", self.zipfile.clone().unwrap());
-            let mut file =
-                File::create(&format!("{}", &zip_path)).expect("failed to create db_path");
+    fn layer_crs(
+        &self,
+        conn: &Connection,
+        table: &str,
+    ) -> (
+        Option<i32>,
+        Option<crs_definitions::Def>,
+        Option<(u16, u16)>,
+    ) {
+        // CRS recorded against the geometry column, falling back to the set crs
+        // The layer CRS wins over a different set crs, the mismatch is passed back
+        let srs = conn.query_row(
+            "SELECT srs.srs_id, srs.organization, srs.organization_coordsys_id
+            FROM gpkg_geometry_columns AS geom
+            JOIN gpkg_spatial_ref_sys AS srs ON geom.srs_id = srs.srs_id
+            WHERE geom.table_name = ?1",
+            [table],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i32>(2)?,
+                ))
+            },
+        );
+        let (srs_id, organization, code) = match srs {
+            Ok(srs) => srs,
+            Err(_) => return (None, self.crs.as_ref().map(coord::copy_crs), None),
+        };
 
-            copy(&mut response, &mut file).expect("failed to copy content to db_path");
+        let layer_crs = match organization.eq_ignore_ascii_case("EPSG") {
+            true => u16::try_from(code)
+                .ok()
+                .and_then(crs_definitions::from_code),
+            false => None,
+        };
 
-            // Unzip archive
-            unzip(&format!("{}", &zip_path), "tmp");
+        match (layer_crs, &self.crs) {
+            (Some(layer_crs), Some(crs)) if layer_crs.code != crs.code => {
+                let mismatch = (layer_crs.code, crs.code);
+                return (Some(srs_id), Some(layer_crs), Some(mismatch));
+            }
+            (Some(layer_crs), _) => return (Some(srs_id), Some(layer_crs), None),
+            (None, crs) => return (Some(srs_id), crs.as_ref().map(coord::copy_crs), None),
         }
     }
 
-    pub fn extract(&self) -> Vec<GeoData> {
-        let _ = &self.get_gdb();
+    pub fn reader(&self) -> Result<GeoReader<'_>, String> {
+        // Open connection to the layer, staging downloads & archives in a temp dir
+        let work_dir = TempDir::new().expect("failed to create working dir");
+        let conn = self.connect(work_dir.path());
+        let table = self.table_name(&conn)?;
+        let (srs_id, crs, crs_mismatch) = self.layer_crs(&conn, &table);
+
+        return Ok(GeoReader {
+            conn,
+            work_dir,
+            db: self,
+            table,
+            srs_id,
+            crs,
+            crs_mismatch,
+        });
+    }
+
+    pub fn extract(&self) -> Result<GeoLayer, String> {
+        return self.extract_filtered(&FeatureFilter::default());
+    }
+
+    pub fn extract_filtered(&self, filter: &FeatureFilter) -> Result<GeoLayer, String> {
+        // Whole (filtered) layer in memory, see reader() to stream large layers
+        let reader = self.reader()?;
         let mut data = vec![];
+        let mut issues = vec![];
 
-        {
-            // Context block ensures db connection is closed & subsiquent deletion can run
-            let conn =
-                Connection::open(&format!("tmp/{}", &self.db)).expect("failed to connect to DB");
-            let mut engine = conn
-                .prepare(&format!("SELECT * FROM {}", &self.table))
-                .expect("SQL prep error");
-            let mut rows = engine.query([]).expect("Row query failed");
+        let mut query = reader.query(filter)?;
+        for (feature, error) in query.features() {
+            if let Some(error) = error {
+                issues.push(GeometryIssue {
+                    uuid: feature.uuid.clone(),
+                    error,
+                });
+            }
+            data.push(feature);
+        }
+        // Release the statement's borrow of the reader before taking its CRS
+        drop(query);
+
+        return Ok(GeoLayer {
+            srs_id: reader.srs_id,
+            crs: reader.crs,
+            crs_mismatch: reader.crs_mismatch,
+            data,
+            issues,
+        });
+    }
+}
+
+pub struct GeoReader<'db> {
+    // Declared before work_dir so the connection closes before the dir is removed
+    conn: Connection,
+    work_dir: TempDir,
+    db: &'db GeoDB,
+    pub table: String,
+    pub srs_id: Option<i32>,
+    pub crs: Option<crs_definitions::Def>,
+    pub crs_mismatch: Option<(u16, u16)>,
+}
 
+impl GeoReader<'_> {
+    pub fn query(&self, filter: &FeatureFilter) -> Result<FeatureQuery<'_>, String> {
+        // Prepare a (filtered) read of the layer, see FeatureQuery::features to stream it
+        let (mut conditions, values, envelope_check) =
+            filter_conditions(&self.conn, &self.table, filter)?;
+
+        // Paged on the integer primary key, views (which have no rowid) may not have one
+        let id_col = feature_id_column(&table_columns(&self.conn, &self.table));
+        let table = quote_ident(&self.table);
+        let query = match &id_col {
+            Some(id_col) => {
+                let id_col = quote_ident(id_col);
+                conditions.push(format!("{} > ?", id_col));
+                format!(
+                    "SELECT * FROM {} WHERE {} ORDER BY {} LIMIT ?",
+                    table,
+                    conditions.join(" AND "),
+                    id_col
+                )
+            }
+            None if conditions.is_empty() => format!("SELECT * FROM {}", table),
+            None => format!("SELECT * FROM {} WHERE {}", table, conditions.join(" AND ")),
+        };
+
+        let engine = self
+            .conn
+            .prepare(&query)
+            .map_err(|error| format!("failed to read {} : {}", self.table, error))?;
+        let (geometry_idx, uuid_idx) = self.db.column_indices(&self.conn, &engine, &self.table)?;
+        let id_idx = match id_col {
+            Some(id_col) => Some(
+                engine
+                    .column_index(&id_col)
+                    .map_err(|_| format!("no {} column in {}", id_col, self.table))?,
+            ),
+            None => None,
+        };
+        let columns: Vec<(String, Option<String>)> = engine
+            .columns()
+            .iter()
+            .map(|column| {
+                (
+                    column.name().to_string(),
+                    column.decl_type().map(|decl_type| decl_type.to_string()),
+                )
+            })
+            .collect();
+
+        return Ok(FeatureQuery {
+            conn: &self.conn,
+            query,
+            engine,
+            values,
+            layout: RowLayout {
+                columns,
+                geometry_idx,
+                uuid_idx,
+                id_idx,
+                bbox: filter.bbox.filter(|_| envelope_check),
+            },
+        });
+    }
+}
+
+fn feature_id_column(columns: &[ColumnInfo]) -> Option<String> {
+    // A sole INTEGER PRIMARY KEY is the rowid, so is indexed & unique - part of a composite key isn't
+    let keys: Vec<&ColumnInfo> = columns.iter().filter(|column| column.primary_key).collect();
+    match keys.as_slice() {
+        [key] if key.data_type.eq_ignore_ascii_case("INTEGER") => return Some(key.name.clone()),
+        _ => return None,
+    }
+}
+
+// Rows read per query by Features, when paging on the feature id
+const FEATURE_BATCH: usize = 1000;
+
+pub struct FeatureQuery<'conn> {
+    conn: &'conn Connection,
+    query: String,
+    // Stepped directly when there's no feature id to page on
+    engine: Statement<'conn>,
+    values: Vec<Value>,
+    layout: RowLayout,
+}
+
+impl FeatureQuery<'_> {
+    pub fn features(&mut self) -> Features<'_> {
+        // Lazily read features, a batch at a time in feature id order, else row by row
+        let FeatureQuery {
+            conn,
+            query,
+            engine,
+            values,
+            layout,
+        } = self;
+        let rows = match layout.id_idx {
+            Some(_) => None,
+            None => Some(
+                engine
+                    .query(params_from_iter(values.iter()))
+                    .expect("Row query failed"),
+            ),
+        };
+
+        return Features {
+            conn,
+            query,
+            values,
+            layout,
+            rows,
+            last_id: i64::MIN,
+            batch_size: FEATURE_BATCH,
+            batch: VecDeque::new(),
+            done: false,
+        };
+    }
+}
+
+struct RowLayout {
+    columns: Vec<(String, Option<String>)>,
+    geometry_idx: usize,
+    uuid_idx: usize,
+    // Integer primary key column, when there is one to page on
+    id_idx: Option<usize>,
+    // Only set when the bbox isn't handled by an RTree
+    bbox: Option<Rect>,
+}
+
+impl RowLayout {
+    fn read_feature(&self, row: &Row) -> Option<(GeoData, Option<GeometryError>)> {
+        // Without an RTree the bbox is checked against each feature's envelope
+        if let Some(bbox) = self.bbox {
+            let envelope =
+                geometry_envelope(row.get_ref(self.geometry_idx).expect("failed to get row"));
+            if !envelope.is_some_and(|envelope| envelope.intersects(&bbox)) {
+                return None;
+            }
+        }
+
+        let uuid = uuid_value(row.get_ref(self.uuid_idx).expect("failed to get uuid"));
+
+        // Every other column is kept as a property
+        let mut properties = Properties::new();
+        for (idx, (name, decl_type)) in self.columns.iter().enumerate() {
+            if idx == self.geometry_idx {
+                continue;
+            }
+            let value = row.get_ref(idx).expect("failed to get property");
+            properties.insert(
+                name.clone(),
+                PropertyValue::from_column(value, decl_type.as_deref()),
+            );
+        }
+
+        // Features without a usable geometry are kept (attributes only) & reported
+        match decode_geometry(row.get_ref(self.geometry_idx).expect("failed to get row")) {
+            Ok(geometry) => return Some((GeoData::new(uuid, Some(geometry), properties), None)),
+            Err(error) => return Some((GeoData::new(uuid, None, properties), Some(error))),
+        }
+    }
+}
+
+pub struct Features<'query> {
+    conn: &'query Connection,
+    query: &'query str,
+    values: &'query [Value],
+    layout: &'query RowLayout,
+    // The single stepped query, when not paging on the feature id
+    rows: Option<Rows<'query>>,
+    last_id: i64,
+    batch_size: usize,
+    batch: VecDeque<(GeoData, Option<GeometryError>)>,
+    done: bool,
+}
+
+impl Features<'_> {
+    fn read_batch(&mut self) {
+        // Next page of rows after the last feature id, keeping memory to one batch
+        let id_idx = self.layout.id_idx.expect("no feature id to page on");
+        let mut engine = self
+            .conn
+            .prepare_cached(self.query)
+            .expect("SQL prep error");
+        let mut values = self.values.to_vec();
+        values.push(Value::Integer(self.last_id));
+        values.push(Value::Integer(self.batch_size as i64));
+        let mut rows = engine
+            .query(params_from_iter(values))
+            .expect("Row query failed");
+
+        let mut count = 0;
+        while let Some(row) = rows.next().expect("while error") {
+            count += 1;
+            self.last_id = row.get(id_idx).expect("failed to get feature id");
+            if let Some(feature) = self.layout.read_feature(row) {
+                self.batch.push_back(feature);
+            }
+        }
+
+        self.done = count < self.batch_size;
+    }
+}
+
+impl Iterator for Features<'_> {
+    // Each feature, with the reason it has no geometry if so
+    type Item = (GeoData, Option<GeometryError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Stepped row by row when there's no feature id to page on
+        if let Some(rows) = &mut self.rows {
             while let Some(row) = rows.next().expect("while error") {
-                // Extract geometry from bytes array
-                let shape_entry: Vec<u8> = row
-                    .get(self.geometry_col_idx as usize)
-                    .expect("failed to get row");
-                let mut bytes_cursor = Cursor::new(&shape_entry);
-                let geometry = FromWkb::from_wkb(&mut bytes_cursor, WkbDialect::Geopackage);
-
-                match geometry {
-                    Ok(Geometry::Point(mp)) => {
-                        data.push(GeoData {
-                            uuid: row
-                                .get(self.uuid_col_idx as usize)
-                                .expect("failed to get uuid"),
-                            point: Some(mp),
-                            polygon: None,
-                            multipolygon: None,
-                        });
-                    }
-                    Ok(Geometry::Polygon(mp)) => {
-                        data.push(GeoData {
-                            uuid: row
-                                .get(self.uuid_col_idx as usize)
-                                .expect("failed to get uuid"),
-                            point: None,
-                            polygon: Some(mp),
-                            multipolygon: None,
-                        });
-                    }
-                    Ok(Geometry::MultiPolygon(mp)) => {
-                        data.push(GeoData {
-                            uuid: row
-                                .get(self.uuid_col_idx as usize)
-                                .expect("failed to get uuid"),
-                            point: None,
-                            polygon: None,
-                            multipolygon: Some(mp),
-                        });
-                    }
-                    _ => (),
+                if let Some(feature) = self.layout.read_feature(row) {
+                    return Some(feature);
                 }
             }
+            return None;
         }
 
-        // Clean up
-        remove_file(&format!("tmp/{}", &self.db)).expect("failed to remove DB from working dir");
-        if self.zipfile.is_some() {
-            remove_file(&format!("tmp/{}", &self.zipfile.clone().unwrap()))
-                .expect("failed to remove zip archive from working dir");
+        loop {
+            if let Some(feature) = self.batch.pop_front() {
+                return Some(feature);
+            }
+            if self.done {
+                return None;
+            }
+            self.read_batch();
         }
-
-        return data;
     }
 }
 
 #[test]
 fn test_example_points() {
     let test = GeoDB {
-        url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string(),
-        zipfile: Some("SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string()),
+        source: GeoSource::Url {
+            url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip"
+                .to_string(),
+            zipfile: Some("SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string()),
+        },
         db: "SEPA_BATHING_WATER_POINTS_BNG.gpkg".to_string(),
-        table: "SEPA_BATHING_WATER_POINTS_BNG".to_string(),
-        crs: crs_definitions::EPSG_27700,
+        table: Some("SEPA_BATHING_WATER_POINTS_BNG".to_string()),
+        crs: Some(crs_definitions::EPSG_27700),
+        uuid_col: Some("bw_url".to_string()),
         geometry_col_idx: 1,
         uuid_col_idx: 8,
     };
@@ -163,12 +1167,15 @@ fn test_example_points() {
 #[test]
 fn test_example_polygons() {
     let test = GeoDB {
-        url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip"
-            .to_string(),
-        zipfile: Some("SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip".to_string()),
+        source: GeoSource::Url {
+            url: "https://map.sepa.org.uk/atom/Data/SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip"
+                .to_string(),
+            zipfile: Some("SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip".to_string()),
+        },
         db: "SEPA_BATHING_WATER_POLYGONS_BNG.gpkg".to_string(),
-        table: "SEPA_BATHING_WATER_POLYGONS_BNG".to_string(),
-        crs: crs_definitions::EPSG_27700,
+        table: Some("SEPA_BATHING_WATER_POLYGONS_BNG".to_string()),
+        crs: Some(crs_definitions::EPSG_27700),
+        uuid_col: Some("bw_url".to_string()),
         geometry_col_idx: 1,
         uuid_col_idx: 8,
     };
@@ -176,19 +1183,903 @@ fn test_example_polygons() {
     assert_eq!(test, GeoDB::example_polygons_db());
 }
 
+#[test]
+fn test_geodata_area() {
+    use geo::{point, polygon};
+
+    let square: Polygon = polygon![
+        (x: 325000.0, y: 673000.0),
+        (x: 326000.0, y: 673000.0),
+        (x: 326000.0, y: 674000.0),
+        (x: 325000.0, y: 674000.0),
+        (x: 325000.0, y: 673000.0),
+    ];
+
+    let site = GeoData::from_geometry("site", MultiPolygon::new(vec![square.clone(), square]));
+    assert_eq!(site.area(&DistanceMethod::Planar), 2_000_000.0);
+    assert_eq!(site.perimeter(&DistanceMethod::Planar), 8_000.0);
+
+    let station = GeoData::from_geometry("station", point!(x: 325000.0, y: 673000.0));
+    assert_eq!(station.area(&DistanceMethod::Planar), 0.0);
+}
+
+#[test]
+fn test_geodata_normalise() {
+    use geo::{Rect, Winding, coord, point, polygon};
+
+    // Clockwise polygon becomes a counter-clockwise multipolygon
+    let mut site = GeoData::from_geometry(
+        "site",
+        polygon![
+            (x: 325000.0, y: 673000.0),
+            (x: 325000.0, y: 674000.0),
+            (x: 326000.0, y: 674000.0),
+            (x: 326000.0, y: 673000.0),
+            (x: 325000.0, y: 673000.0),
+        ],
+    );
+    assert!(site.polygon().is_some());
+    let area = site.area(&DistanceMethod::Planar);
+
+    site.normalise_polygons();
+    assert!(site.polygon().is_none());
+    let multipolygon = site.multipolygon().unwrap();
+    assert_eq!(multipolygon.0.len(), 1);
+    assert!(multipolygon.0[0].exterior().is_ccw());
+    assert_eq!(site.area(&DistanceMethod::Planar), area);
+
+    // Rects are areal too, points are left alone
+    let mut extent = GeoData::from_geometry(
+        "extent",
+        Rect::new(coord! { x: 0.0, y: 0.0 }, coord! { x: 10.0, y: 10.0 }),
+    );
+    assert_eq!(extent.area(&DistanceMethod::Planar), 100.0);
+    extent.normalise_polygons();
+    assert!(extent.multipolygon().is_some());
+
+    let mut station = GeoData::from_geometry("station", point!(x: 5.0, y: 5.0));
+    station.normalise_polygons();
+    assert_eq!(station.point(), Some(&point!(x: 5.0, y: 5.0)));
+    assert!(station.to_multipolygon().is_none());
+
+    // Features measure against each other whatever their geometry
+    assert_eq!(
+        dist::feature_distance(&station, &extent, &DistanceMethod::Planar),
+        0.0
+    );
+}
+
+#[test]
+fn test_property_values() {
+    // Dates are text columns declared DATE / DATETIME
+    let date = PropertyValue::from_column(ValueRef::Text(b"2024-05-01"), Some("DATE"));
+    assert_eq!(date, PropertyValue::Date("2024-05-01".to_string()));
+    assert_eq!(date.as_date(), Some("2024-05-01"));
+    assert_eq!(date.as_str(), Some("2024-05-01"));
+
+    let text = PropertyValue::from_column(ValueRef::Text(b"Excellent"), Some("TEXT(8000)"));
+    assert_eq!(text.as_date(), None);
+    assert_eq!(text.as_f64(), None);
+
+    let blob = PropertyValue::from_column(ValueRef::Blob(&[1, 2, 3]), None);
+    assert_eq!(blob.as_bytes(), Some(&[1_u8, 2, 3][..]));
+
+    // Serde round trip keeps the types
+    let mut properties = Properties::new();
+    properties.insert("description".to_string(), text);
+    properties.insert("sampled".to_string(), date);
+    properties.insert("year".to_string(), PropertyValue::Integer(2024));
+    properties.insert("score".to_string(), PropertyValue::Real(0.5));
+    properties.insert("class_id".to_string(), PropertyValue::Null);
+
+    let json = serde_json::to_string(&properties).expect("failed to serialise properties");
+    let parsed: Properties = serde_json::from_str(&json).expect("failed to parse properties");
+    assert_eq!(parsed, properties);
+}
+
+#[cfg(test)]
+fn example_mixed_gpkg(dir: &Path) -> String {
+    use geo::{GeometryCollection, MultiPoint, line_string, point};
+    use geozero::{CoordDimensions, ToWkb};
+
+    // Minimal gpkg holding a line, multipoint & collection plus null, broken & empty geometries
+    let db_path = dir
+        .join("mixed.gpkg")
+        .to_str()
+        .expect("failed to convert path")
+        .to_string();
+    let conn = Connection::open(&db_path).expect("failed to create test DB");
+    conn.execute_batch(
+        "CREATE TABLE gpkg_spatial_ref_sys (srs_name TEXT, srs_id INTEGER PRIMARY KEY,
+            organization TEXT, organization_coordsys_id INTEGER, definition TEXT);
+        INSERT INTO gpkg_spatial_ref_sys VALUES ('British_National_Grid', 27700, 'EPSG', 27700, '');
+        CREATE TABLE gpkg_geometry_columns (table_name TEXT, column_name TEXT,
+            geometry_type_name TEXT, srs_id INTEGER, z INTEGER, m INTEGER);
+        INSERT INTO gpkg_geometry_columns VALUES ('mixed', 'geom', 'GEOMETRY', 27700, 0, 0);
+        CREATE TABLE mixed (fid INTEGER PRIMARY KEY, geom BLOB, name TEXT);",
+    )
+    .expect("failed to create test tables");
+
+    let geometries: Vec<Geometry> = vec![
+        Geometry::LineString(line_string![(x: 325000.0, y: 673000.0), (x: 326000.0, y: 674000.0)]),
+        Geometry::MultiPoint(MultiPoint::new(vec![
+            point!(x: 325000.0, y: 673000.0),
+            point!(x: 326000.0, y: 674000.0),
+        ])),
+        Geometry::GeometryCollection(GeometryCollection::new_from(vec![Geometry::Point(
+            point!(x: 325000.0, y: 673000.0),
+        )])),
+    ];
+    for (idx, geometry) in geometries.iter().enumerate() {
+        let wkb = geometry
+            .to_gpkg_wkb(CoordDimensions::xy(), Some(27700), vec![])
+            .expect("failed to encode geometry");
+        conn.execute(
+            "INSERT INTO mixed (geom, name) VALUES (?1, ?2)",
+            (wkb, format!("feature_{}", idx)),
+        )
+        .expect("failed to insert geometry");
+    }
+
+    // Empty flag set in the gpkg header, ahead of an empty collection
+    let empty: Vec<u8> = vec![
+        b'G',
+        b'P',
+        0,
+        0b0001_0001,
+        0x6c,
+        0x6c,
+        0,
+        0,
+        1,
+        7,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ];
+    conn.execute(
+        "INSERT INTO mixed (geom, name) VALUES (NULL, 'null'), (X'00010203', 'broken'), (?1, 'empty')",
+        [empty],
+    )
+    .expect("failed to insert bad geometries");
+
+    return db_path;
+}
+
+#[test]
+fn test_extract_geometry_types() {
+    let binding = TempDir::new().expect("failed to create test dir");
+    let layer = GeoDB {
+        source: GeoSource::Gpkg(example_mixed_gpkg(binding.path())),
+        db: "mixed.gpkg".to_string(),
+        table: Some("mixed".to_string()),
+        crs: None,
+        uuid_col: Some("name".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 1,
+    }
+    .extract()
+    .unwrap();
+
+    // Every feature is kept, only the good geometries are set
+    assert_eq!(layer.data.len(), 6);
+    assert!(matches!(
+        layer.data[0].geometry,
+        Some(Geometry::LineString(_))
+    ));
+    assert!(matches!(
+        layer.data[1].geometry,
+        Some(Geometry::MultiPoint(_))
+    ));
+    assert!(matches!(
+        layer.data[2].geometry,
+        Some(Geometry::GeometryCollection(_))
+    ));
+    assert!(layer.data[3..].iter().all(|row| row.geometry.is_none()));
+
+    // Bad geometries are reported against their feature
+    let issues: Vec<(&str, &GeometryError)> = layer
+        .issues
+        .iter()
+        .map(|issue| (issue.uuid.as_str(), &issue.error))
+        .collect();
+    assert_eq!(issues.len(), 3);
+    assert_eq!(issues[0], ("null", &GeometryError::Null));
+    assert!(matches!(
+        issues[1],
+        ("broken", GeometryError::Undecodable(_))
+    ));
+    assert_eq!(issues[2], ("empty", &GeometryError::Empty));
+}
+
+#[test]
+fn test_layers() {
+    let layers = GeoDB::example_polygons_local_db().layers();
+    assert_eq!(layers.len(), 1);
+
+    let layer = &layers[0];
+    assert_eq!(layer.table, "SEPA_BATHING_WATER_POLYGONS_BNG");
+    assert_eq!(layer.data_type, "features");
+    assert_eq!(layer.geometry_column.as_deref(), Some("Shape"));
+    assert_eq!(layer.geometry_type.as_deref(), Some("MULTIPOLYGON"));
+    assert_eq!(layer.srs_id, Some(27700));
+    assert_eq!(layer.feature_count, 89);
+
+    // No bbox in gpkg_contents, taken from the RTree - bathing waters sit within Scotland's BNG extent
+    let bbox = layer.bbox.unwrap();
+    assert!(bbox.min().x > 0.0 && bbox.max().x < 500_000.0);
+    assert!(bbox.min().y > 500_000.0 && bbox.max().y < 1_000_000.0);
+
+    let names: Vec<&str> = layer
+        .columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "OBJECTID",
+            "Shape",
+            "bw_id",
+            "description",
+            "year",
+            "current_yn",
+            "class_id",
+            "class_description",
+            "bw_url"
+        ]
+    );
+    assert!(layer.columns[0].primary_key && layer.columns[0].not_null);
+    assert_eq!(layer.columns[1].data_type, "MULTIPOLYGON");
+
+    // Single layer gpkgs don't need a table name
+    let untabled = GeoDB {
+        table: None,
+        ..GeoDB::example_polygons_local_db()
+    };
+    assert_eq!(untabled.extract().unwrap().data.len(), 89);
+}
+
+#[test]
+fn test_layer_names() {
+    use crate::writer::GeoWriter;
+    use geo::point;
+
+    // Quotes are legal in gpkg table & column names
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding.path().join("names.gpkg");
+    let writer = GeoWriter {
+        path: path.to_str().unwrap().to_string(),
+        table: "sites".to_string(),
+        crs: crs_definitions::EPSG_4326,
+        geometry_col: "geom".to_string(),
+        uuid_col: Some("name".to_string()),
+        spatial_index: true,
+    };
+    let sites = vec![
+        GeoData::from_geometry("station", point!(x: -3.2, y: 55.95)),
+        GeoData::from_geometry("outfall", point!(x: -3.1, y: 55.9)),
+    ];
+    writer.write(&sites).expect("failed to write gpkg");
+
+    let conn = Connection::open(&path).expect("failed to open gpkg");
+    conn.execute_batch(
+        "PRAGMA foreign_keys = OFF;
+        ALTER TABLE sites RENAME TO \"o\"\"dd\";
+        ALTER TABLE \"o\"\"dd\" RENAME COLUMN name TO \"na\"\"me\";
+        ALTER TABLE rtree_sites_geom RENAME TO \"rtree_o\"\"dd_geom\";
+        UPDATE gpkg_contents SET table_name = 'o\"dd', identifier = 'o\"dd', min_x = NULL;
+        UPDATE gpkg_geometry_columns SET table_name = 'o\"dd';
+        CREATE TABLE notes (id INTEGER PRIMARY KEY, note TEXT);
+        INSERT INTO gpkg_contents (table_name, data_type, identifier) VALUES ('notes', 'attributes', 'notes');",
+    )
+    .expect("failed to rename layer");
+    drop(conn);
+
+    let db = GeoDB {
+        source: GeoSource::Gpkg(writer.path.clone()),
+        db: "names.gpkg".to_string(),
+        table: None,
+        crs: None,
+        uuid_col: Some("na\"me".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let layers = db.layers();
+    assert_eq!(layers.len(), 2);
+    assert_eq!(layers[0].table, "notes");
+    assert_eq!(layers[1].table, "o\"dd");
+    assert_eq!(layers[1].feature_count, 2);
+    assert!(layers[1].bbox.is_some());
+
+    // The attribute table doesn't count towards the default layer
+    let layer = db.extract().unwrap();
+    assert_eq!(layer.data.len(), 2);
+    for (row, site) in layer.data.iter().zip(&sites) {
+        assert_eq!((&row.uuid, &row.geometry), (&site.uuid, &site.geometry));
+    }
+
+    // Two feature layers need a table name
+    let second = GeoWriter {
+        table: "stations".to_string(),
+        ..writer
+    };
+    second.write(&sites).expect("failed to write gpkg");
+    assert_eq!(
+        db.extract().err(),
+        Some("names.gpkg holds 2 feature layers, a table name is needed".to_string())
+    );
+}
+
 #[test]
 fn test_extract() {
+    // Bundled zip archive
+    let layer = GeoDB::example_polygons_local_db().extract().unwrap();
+    assert_eq!(layer.srs_id, Some(27700));
+    assert_eq!(layer.crs, Some(crs_definitions::EPSG_27700));
+    assert_eq!(layer.crs_mismatch, None);
+    assert!(layer.issues.is_empty());
+    assert!(layer.check_crs(&crs_definitions::EPSG_27700).is_ok());
+    assert!(layer.check_crs(&crs_definitions::EPSG_4326).is_err());
+
+    let test = layer.data;
+    assert_eq!(test.len(), 89);
+    for row in &test {
+        assert!(row.point().is_none());
+        assert!(row.multipolygon().is_some());
+    }
+
+    // Same gpkg read in place
+    let binding = TempDir::new().expect("failed to create test dir");
+    let dest = binding
+        .path()
+        .to_str()
+        .expect("failed to extract tmp test path");
+    unzip("data/SEPA_BATHING_WATER_POLYGONS_BNG_gpkg.zip", dest);
+    let db_path = format!("{}/SEPA_BATHING_WATER_POLYGONS_BNG.gpkg", dest);
+
+    let local = GeoDB {
+        source: GeoSource::Gpkg(db_path.clone()),
+        ..GeoDB::example_polygons_db()
+    };
+    assert_eq!(local.extract().unwrap().data, test);
+    assert!(std::fs::exists(&db_path).expect("failed to check db path"));
+
+    // Geometry column comes from gpkg_geometry_columns, not the fallback index
+    let by_name = GeoDB {
+        source: GeoSource::Gpkg(db_path.clone()),
+        uuid_col: Some("OBJECTID".to_string()),
+        geometry_col_idx: 99,
+        uuid_col_idx: 99,
+        ..GeoDB::example_polygons_db()
+    };
+    let test_by_name = by_name.extract().unwrap().data;
+    assert_eq!(test_by_name.len(), 89);
+    assert_eq!(test_by_name[0].uuid, "1");
+    assert_eq!(test_by_name[0].geometry, test[0].geometry);
+
+    // Attribute columns come through typed, the geometry column doesn't
+    let aberdeen = &test[0];
+    assert_eq!(
+        aberdeen.property("description"),
+        Some(&PropertyValue::Text("Aberdeen".to_string()))
+    );
+    assert_eq!(
+        aberdeen.property("year").and_then(|year| year.as_i64()),
+        Some(2024)
+    );
+    assert_eq!(
+        aberdeen.property("OBJECTID").and_then(|id| id.as_f64()),
+        Some(1.0)
+    );
+    assert!(
+        aberdeen
+            .property("class_id")
+            .is_some_and(|class| class.is_null())
+    );
+    assert!(aberdeen.property("Shape").is_none());
+    assert_eq!(aberdeen.properties.len(), 8);
+
+    // Layer CRS wins over a mismatched caller CRS, & fills in a missing one
+    let mismatched = GeoDB {
+        source: GeoSource::Gpkg(db_path.clone()),
+        crs: Some(crs_definitions::EPSG_4326),
+        ..GeoDB::example_polygons_db()
+    };
+    let mismatched_layer = mismatched.extract().unwrap();
+    assert_eq!(mismatched_layer.crs, Some(crs_definitions::EPSG_27700));
+    assert_eq!(mismatched_layer.crs_mismatch, Some((27700, 4326)));
+
+    let unset = GeoDB {
+        source: GeoSource::Gpkg(db_path),
+        crs: None,
+        ..GeoDB::example_polygons_db()
+    };
+    let unset_layer = unset.extract().unwrap();
+    assert_eq!(unset_layer.crs, Some(crs_definitions::EPSG_27700));
+    assert_eq!(unset_layer.crs_mismatch, None);
+
+    // No CRS metadata at all is distinct from the undefined srs_id -1
+    let no_metadata = GeoLayer {
+        srs_id: None,
+        crs: None,
+        crs_mismatch: None,
+        data: vec![],
+        issues: vec![],
+    };
+    assert_eq!(
+        no_metadata.check_crs(&crs_definitions::EPSG_27700),
+        Err("layer has no CRS metadata".to_string())
+    );
+}
+
+#[test]
+fn test_extract_filtered() {
+    use crate::writer::GeoWriter;
+
+    // Bbox around the first bathing water, checked against a full extract
+    let db = GeoDB::example_polygons_local_db();
+    let full = db.extract().unwrap();
+    let first = full.data[0].bounding_rect().unwrap();
+    let bbox = Rect::new(
+        Coord {
+            x: first.min().x - 5000.0,
+            y: first.min().y - 5000.0,
+        },
+        Coord {
+            x: first.max().x + 5000.0,
+            y: first.max().y + 5000.0,
+        },
+    );
+    let expected: Vec<String> = full
+        .data
+        .iter()
+        .filter(|row| {
+            row.bounding_rect()
+                .is_some_and(|rect| rect.intersects(&bbox))
+        })
+        .map(|row| row.uuid.clone())
+        .collect();
+    assert!(!expected.is_empty() && expected.len() < full.data.len());
+
+    // RTree lookup
+    let filter = FeatureFilter {
+        bbox: Some(bbox),
+        attributes: vec![],
+    };
+    let uuids = |layer: GeoLayer| -> Vec<String> {
+        return layer.data.into_iter().map(|row| row.uuid).collect();
+    };
+    assert_eq!(uuids(db.extract_filtered(&filter).unwrap()), expected);
+
+    // Header envelopes when the gpkg has no RTree
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding.path().join("no_index.gpkg");
+    let writer = GeoWriter {
+        path: path.to_str().unwrap().to_string(),
+        table: "bathing_waters".to_string(),
+        crs: crs_definitions::EPSG_27700,
+        geometry_col: "geom".to_string(),
+        uuid_col: None,
+        spatial_index: false,
+    };
+    writer.write(&full.data).expect("failed to write gpkg");
+    let no_index = GeoDB {
+        source: GeoSource::Gpkg(writer.path.clone()),
+        table: None,
+        ..GeoDB::example_polygons_db()
+    };
+    assert_eq!(uuids(no_index.extract_filtered(&filter).unwrap()), expected);
+
+    // Decoded geometry bounds when there's no header envelope either, unusable geometries dropped
+    let mixed = GeoDB {
+        source: GeoSource::Gpkg(example_mixed_gpkg(binding.path())),
+        db: "mixed.gpkg".to_string(),
+        table: Some("mixed".to_string()),
+        crs: None,
+        uuid_col: Some("name".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let mixed_filter = FeatureFilter {
+        bbox: Some(Rect::new(
+            Coord {
+                x: 325900.0,
+                y: 673900.0,
+            },
+            Coord {
+                x: 327000.0,
+                y: 675000.0,
+            },
+        )),
+        attributes: vec![],
+    };
+    assert_eq!(
+        uuids(mixed.extract_filtered(&mixed_filter).unwrap()),
+        vec!["feature_0", "feature_1"]
+    );
+
+    // Attribute filters, combined with the bbox
+    let filter = FeatureFilter {
+        bbox: Some(bbox),
+        attributes: vec![
+            AttributeFilter {
+                column: "year".to_string(),
+                condition: Condition::Equal(PropertyValue::Integer(2024)),
+            },
+            AttributeFilter {
+                column: "class_id".to_string(),
+                condition: Condition::IsNull,
+            },
+        ],
+    };
+    let layer = db.extract_filtered(&filter).unwrap();
+    assert!(!layer.data.is_empty());
+    assert!(layer.data.iter().all(|row| expected.contains(&row.uuid)
+        && row.property("year") == Some(&PropertyValue::Integer(2024))
+        && row.property("class_id") == Some(&PropertyValue::Null)));
+
+    let aberdeen = FeatureFilter {
+        bbox: None,
+        attributes: vec![AttributeFilter {
+            column: "description".to_string(),
+            condition: Condition::Like("Aberdeen%".to_string()),
+        }],
+    };
+    assert_eq!(
+        db.extract_filtered(&aberdeen).unwrap().data[0].uuid,
+        full.data[0].uuid
+    );
+
+    let ids = FeatureFilter {
+        bbox: None,
+        attributes: vec![AttributeFilter {
+            column: "OBJECTID".to_string(),
+            condition: Condition::In(vec![PropertyValue::Integer(1), PropertyValue::Integer(3)]),
+        }],
+    };
+    assert_eq!(db.extract_filtered(&ids).unwrap().data.len(), 2);
+
+    // Values are bound, not spliced into the SQL
+    let injection = FeatureFilter {
+        bbox: None,
+        attributes: vec![AttributeFilter {
+            column: "description".to_string(),
+            condition: Condition::Equal(PropertyValue::Text("x' OR '1'='1".to_string())),
+        }],
+    };
+    assert!(db.extract_filtered(&injection).unwrap().data.is_empty());
+}
+
+#[test]
+fn test_extract_filtered_quoted() {
+    use crate::writer::GeoWriter;
+    use geo::point;
+
+    // Quoted table, geometry & attribute names, through the RTree lookup
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding.path().join("quoted.gpkg");
+    let mut gauge = GeoData::from_geometry("gauge", point!(x: -3.2, y: 55.95));
+    gauge
+        .properties
+        .insert("co\"de".to_string(), PropertyValue::Text("A1".to_string()));
+    let mut weir = GeoData::from_geometry("weir", point!(x: -3.1, y: 55.9));
+    weir.properties
+        .insert("co\"de".to_string(), PropertyValue::Text("B2".to_string()));
+    let mut far = GeoData::from_geometry("far", point!(x: -5.0, y: 57.0));
+    far.properties
+        .insert("co\"de".to_string(), PropertyValue::Text("A1".to_string()));
+    let writer = GeoWriter {
+        path: path.to_str().unwrap().to_string(),
+        table: "we\"ird".to_string(),
+        crs: crs_definitions::EPSG_4326,
+        geometry_col: "ge\"om".to_string(),
+        uuid_col: Some("name".to_string()),
+        spatial_index: true,
+    };
+    writer
+        .write(&[gauge, weir, far])
+        .expect("failed to write gpkg");
+
+    let db = GeoDB {
+        source: GeoSource::Gpkg(writer.path.clone()),
+        db: "quoted.gpkg".to_string(),
+        table: None,
+        crs: None,
+        uuid_col: Some("name".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let filter = FeatureFilter {
+        bbox: Some(Rect::new(
+            Coord { x: -3.5, y: 55.5 },
+            Coord { x: -3.0, y: 56.0 },
+        )),
+        attributes: vec![AttributeFilter {
+            column: "co\"de".to_string(),
+            condition: Condition::Equal(PropertyValue::Text("A1".to_string())),
+        }],
+    };
+    let layer = db.extract_filtered(&filter).unwrap();
+    let uuids: Vec<&str> = layer.data.iter().map(|row| row.uuid.as_str()).collect();
+    assert_eq!(uuids, vec!["gauge"]);
+}
+
+#[test]
+fn test_extract_filtered_unknown_column() {
+    // Unknown columns are rejected rather than spliced into the SQL
+    let db = GeoDB::example_polygons_local_db();
+    let filter = FeatureFilter {
+        bbox: None,
+        attributes: vec![AttributeFilter {
+            column: "year\" OR 1=1 --".to_string(),
+            condition: Condition::NotNull,
+        }],
+    };
+    assert_eq!(
+        db.extract_filtered(&filter).err(),
+        Some("no year\" OR 1=1 -- column in SEPA_BATHING_WATER_POLYGONS_BNG".to_string())
+    );
+    assert!(db.reader().unwrap().query(&filter).is_err());
+}
+
+#[test]
+fn test_features() {
+    // Streamed features match the collected layer, across batch boundaries
+    let db = GeoDB::example_polygons_local_db();
+    let layer = db.extract().unwrap();
+    let reader = db.reader().unwrap();
+    assert_eq!(reader.table, "SEPA_BATHING_WATER_POLYGONS_BNG");
+    assert_eq!(reader.srs_id, Some(27700));
+
+    let mut query = reader.query(&FeatureFilter::default()).unwrap();
+    let mut features = query.features();
+    features.batch_size = 10;
+    let streamed: Vec<GeoData> = features.map(|(feature, _)| feature).collect();
+    assert_eq!(streamed, layer.data);
+
+    // Lazy, only the first batch is read
+    let mut query = reader.query(&FeatureFilter::default()).unwrap();
+    let mut features = query.features();
+    features.batch_size = 10;
+    let first: Vec<GeoData> = features
+        .by_ref()
+        .take(3)
+        .map(|(feature, _)| feature)
+        .collect();
+    assert_eq!(first, layer.data[0..3]);
+    assert_eq!(features.batch.len(), 7);
+    assert_eq!(features.count(), 86);
+
+    // Envelope bbox check & geometry errors within small batches
+    let binding = TempDir::new().expect("failed to create test dir");
+    let mixed = GeoDB {
+        source: GeoSource::Gpkg(example_mixed_gpkg(binding.path())),
+        db: "mixed.gpkg".to_string(),
+        table: Some("mixed".to_string()),
+        crs: None,
+        uuid_col: Some("name".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let reader = mixed.reader().unwrap();
+    let mut query = reader.query(&FeatureFilter::default()).unwrap();
+    let mut features = query.features();
+    features.batch_size = 2;
+    let errors: Vec<Option<GeometryError>> = features.map(|(_, error)| error).collect();
+    assert_eq!(errors.len(), 6);
+    assert_eq!(errors[3], Some(GeometryError::Null));
+    assert_eq!(errors[5], Some(GeometryError::Empty));
+
+    let filter = FeatureFilter {
+        bbox: Some(Rect::new(
+            Coord {
+                x: 325900.0,
+                y: 673900.0,
+            },
+            Coord {
+                x: 327000.0,
+                y: 675000.0,
+            },
+        )),
+        attributes: vec![],
+    };
+    let mut query = reader.query(&filter).unwrap();
+    let mut features = query.features();
+    features.batch_size = 1;
+    let uuids: Vec<String> = features.map(|(feature, _)| feature.uuid).collect();
+    assert_eq!(uuids, vec!["feature_0", "feature_1"]);
+}
+
+#[test]
+fn test_extract_view() {
+    use crate::writer::GeoWriter;
+
+    // Feature layers may be views, which have no rowid or primary key to page on
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding.path().join("views.gpkg");
+    let writer = GeoWriter {
+        path: path.to_str().unwrap().to_string(),
+        table: "bathing_waters".to_string(),
+        crs: crs_definitions::EPSG_27700,
+        geometry_col: "geom".to_string(),
+        uuid_col: None,
+        spatial_index: true,
+    };
+    let full = GeoDB::example_polygons_local_db().extract().unwrap();
+    writer.write(&full.data).expect("failed to write gpkg");
+
+    let conn = Connection::open(&path).expect("failed to open gpkg");
+    conn.execute_batch(
+        "CREATE VIEW later AS SELECT * FROM bathing_waters WHERE OBJECTID > 40;
+        INSERT INTO gpkg_contents (table_name, data_type, identifier, srs_id)
+        VALUES ('later', 'features', 'later', 27700);
+        INSERT INTO gpkg_geometry_columns (table_name, column_name, geometry_type_name, srs_id, z, m)
+        VALUES ('later', 'geom', 'MULTIPOLYGON', 27700, 0, 0);",
+    )
+    .expect("failed to create view");
+    drop(conn);
+
+    let table = GeoDB {
+        source: GeoSource::Gpkg(writer.path.clone()),
+        table: Some("bathing_waters".to_string()),
+        ..GeoDB::example_polygons_db()
+    };
+    let view = GeoDB {
+        source: GeoSource::Gpkg(writer.path.clone()),
+        table: Some("later".to_string()),
+        ..GeoDB::example_polygons_db()
+    };
+    let later = FeatureFilter {
+        bbox: None,
+        attributes: vec![AttributeFilter {
+            column: "OBJECTID".to_string(),
+            condition: Condition::Greater(PropertyValue::Integer(40)),
+        }],
+    };
+    let expected = table.extract_filtered(&later).unwrap();
+    assert!(!expected.data.is_empty() && expected.data.len() < full.data.len());
+
+    let layer = view.extract().unwrap();
+    assert_eq!(layer.srs_id, Some(27700));
+    assert_eq!(layer.data, expected.data);
+
+    // Bbox checked against envelopes, as a view has no RTree of its own
+    let first = expected.data[0].bounding_rect().unwrap();
+    let filter = FeatureFilter {
+        bbox: Some(first),
+        attributes: vec![],
+    };
+    let uuids = |layer: GeoLayer| -> Vec<String> {
+        return layer.data.into_iter().map(|row| row.uuid).collect();
+    };
+    assert_eq!(
+        uuids(view.extract_filtered(&filter).unwrap()),
+        uuids(
+            table
+                .extract_filtered(&FeatureFilter {
+                    bbox: Some(first),
+                    ..later
+                })
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_features_composite_key() {
+    use crate::writer::GeoWriter;
+    use geo::point;
+
+    // Part of a composite key isn't unique, so can't be paged on
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding.path().join("readings.gpkg");
+    let writer = GeoWriter {
+        path: path.to_str().unwrap().to_string(),
+        table: "sites".to_string(),
+        crs: crs_definitions::EPSG_4326,
+        geometry_col: "geom".to_string(),
+        uuid_col: None,
+        spatial_index: false,
+    };
+    let sites: Vec<GeoData> = (0..4)
+        .map(|idx| {
+            GeoData::from_geometry(
+                &idx.to_string(),
+                point!(x: -3.2 + idx as f64 * 0.1, y: 55.9),
+            )
+        })
+        .collect();
+    writer.write(&sites).expect("failed to write gpkg");
+
+    let conn = Connection::open(&path).expect("failed to open gpkg");
+    conn.execute_batch(
+        "CREATE TABLE readings (site INTEGER, day INTEGER, tag BLOB, geom POINT, PRIMARY KEY (site, day));
+        INSERT INTO readings SELECT (fid + 1) / 2, fid, randomblob(2), geom FROM sites;
+        UPDATE readings SET tag = X'0A0B' WHERE day = 1;
+        INSERT INTO gpkg_contents (table_name, data_type, identifier, srs_id)
+        VALUES ('readings', 'features', 'readings', 4326);
+        INSERT INTO gpkg_geometry_columns (table_name, column_name, geometry_type_name, srs_id, z, m)
+        VALUES ('readings', 'geom', 'POINT', 4326, 0, 0);",
+    )
+    .expect("failed to create readings");
+    drop(conn);
+
+    let layer = |table: &str, uuid_col: Option<&str>, uuid_col_idx: i32| GeoDB {
+        source: GeoSource::Gpkg(writer.path.clone()),
+        db: "readings.gpkg".to_string(),
+        table: Some(table.to_string()),
+        crs: None,
+        uuid_col: uuid_col.map(|column| column.to_string()),
+        uuid_col_idx,
+        geometry_col_idx: 0,
+    };
+
+    // Stepped in a single query, whatever the batch size
+    let readings = layer("readings", Some("tag"), 0);
+    let reader = readings.reader().unwrap();
+    let mut query = reader.query(&FeatureFilter::default()).unwrap();
+    let mut features = query.features();
+    features.batch_size = 1;
+    let days: Vec<PropertyValue> = features
+        .map(|(feature, _)| feature.properties["day"].clone())
+        .collect();
+    assert_eq!(
+        days,
+        (1..=4).map(PropertyValue::Integer).collect::<Vec<_>>()
+    );
+
+    // Blob ids read as hex
+    assert_eq!(readings.extract().unwrap().data[0].uuid, "0a0b");
+
+    // Bad ids, tables & indices are errors rather than panics
+    assert_eq!(
+        layer("readings", Some("station"), 0).extract().err(),
+        Some("no station column in readings".to_string())
+    );
+    assert!(layer("gauges", None, 0).extract().is_err());
+    assert_eq!(
+        layer("sites", None, 20).extract().err(),
+        Some("no column 20 in sites".to_string())
+    );
+}
+
+#[test]
+fn test_missing_gpkg() {
+    use std::panic::catch_unwind;
+
+    // Mistyped path fails without leaving an empty gpkg behind
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding.path().join("mistyped.gpkg");
+    let db = GeoDB {
+        source: GeoSource::Gpkg(path.to_str().unwrap().to_string()),
+        ..GeoDB::example_polygons_db()
+    };
+
+    assert!(catch_unwind(|| db.layers()).is_err());
+    assert!(catch_unwind(|| db.extract()).is_err());
+    assert!(!path.exists());
+}
+
+// Needs network access : cargo test test_extract_url -- --ignored
+#[test]
+#[ignore]
+fn test_extract_url() {
     // All points dataset test
-    let test = GeoDB::example_points_db().extract();
+    let test = GeoDB::example_points_db().extract().unwrap().data;
     for row in test {
-        assert!(row.point.is_some());
-        assert!(row.multipolygon.is_none());
+        assert!(row.point().is_some());
+        assert!(row.multipolygon().is_none());
     }
 
     // All polygon dataset test
-    let test = GeoDB::example_polygons_db().extract();
+    let test = GeoDB::example_polygons_db().extract().unwrap().data;
     for row in test {
-        assert!(row.point.is_none());
-        assert!(row.multipolygon.is_some());
+        assert!(row.point().is_none());
+        assert!(row.multipolygon().is_some());
     }
 }
@@ -0,0 +1,545 @@
+// Spatial Index (R-tree) for GeoData collections
+use crate::dist::{self, DistanceMethod, Ellipsoid};
+use crate::geodb::GeoData;
+use geo::{BoundingRect, Coord, Geometry, Intersects, Point, Rect};
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{AABB, RTree};
+
+pub type IndexEntry = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+#[derive(Debug, PartialEq)]
+pub struct Snap {
+    pub uuid: String,
+    pub target_uuid: String,
+    pub point: Point,
+    pub snapped: Point,
+    pub distance: f64,
+}
+
+pub struct GeoIndex {
+    pub data: Vec<GeoData>,
+    tree: RTree<IndexEntry>,
+    // Co-ord extent of the envelopes, which bounds how far apart features can be
+    extent: Option<Rect>,
+}
+
+impl GeoIndex {
+    pub fn new(data: Vec<GeoData>) -> GeoIndex {
+        // Features without a geometry are kept in data but never returned by queries
+        let tree = RTree::bulk_load(envelope_entries(&data));
+        let extent = match tree.size() {
+            0 => None,
+            _ => {
+                let envelope = tree.root().envelope();
+                Some(Rect::new(
+                    Coord::from(envelope.lower()),
+                    Coord::from(envelope.upper()),
+                ))
+            }
+        };
+
+        return GeoIndex { data, tree, extent };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.tree.size();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.tree.size() == 0;
+    }
+
+    pub fn query_bbox(&self, bbox: &Rect) -> Vec<&GeoData> {
+        // Features whose bounding box intersects bbox (data co-ords)
+        return locate_rect(&self.tree, bbox)
+            .map(|idx| &self.data[idx])
+            .collect();
+    }
+
+    pub fn nearest(
+        &self,
+        point: &Point,
+        k: usize,
+        method: &DistanceMethod,
+    ) -> Vec<(&GeoData, f64)> {
+        // k nearest features to point by exact method distance (m), closest first
+        let lower_bound = self.envelope_lower_bound(point, method);
+        let mut nearest: Vec<(usize, f64)> = vec![];
+
+        if k == 0 {
+            return vec![];
+        }
+
+        // Envelopes come back in order of co-ord distance, which (when bounded) bounds the exact distance
+        for (entry, envelope_dist_2) in self
+            .tree
+            .nearest_neighbor_iter_with_distance_2(&[point.x(), point.y()])
+        {
+            if let Some(lower_bound) = &lower_bound
+                && nearest.len() == k
+                && lower_bound(envelope_dist_2.sqrt()) > nearest[k - 1].1
+            {
+                break;
+            }
+
+            let dist = self.data[entry.data].distance_to_point(point, method);
+            let pos = nearest.partition_point(|(_, nearest_dist)| *nearest_dist <= dist);
+            nearest.insert(pos, (entry.data, dist));
+            nearest.truncate(k);
+        }
+
+        return nearest
+            .into_iter()
+            .map(|(idx, dist)| (&self.data[idx], dist))
+            .collect();
+    }
+
+    pub fn within_distance(
+        &self,
+        geometry: &Geometry,
+        distance: f64,
+        method: &DistanceMethod,
+    ) -> Vec<(&GeoData, f64)> {
+        // All features within distance (m) of geometry, closest first
+        let rect = match search_rect(geometry, distance, method) {
+            Some(rect) => rect,
+            None => return vec![],
+        };
+
+        let candidates = self.query_bbox(&rect);
+        return refine_within_distance(candidates, geometry, distance, method);
+    }
+
+    pub fn snap_point(
+        &self,
+        point: &Point,
+        tolerance: f64,
+        method: &DistanceMethod,
+    ) -> Option<(&GeoData, Point, f64)> {
+        // Nearest line / boundary edge within tolerance (m) : target feature, snapped point & distance
+        let rect = search_rect(&Geometry::Point(*point), tolerance, method)?;
+        let mut snap: Option<(&GeoData, Point, f64)> = None;
+
+        for target in self.query_bbox(&rect) {
+            let closest = target
+                .geometry
+                .as_ref()
+                .and_then(|geometry| dist::closest_point_on_edges(point, geometry, method));
+
+            if let Some((snapped, distance)) = closest
+                && distance <= tolerance
+                && snap.is_none_or(|(_, _, best)| distance < best)
+            {
+                snap = Some((target, snapped, distance));
+            }
+        }
+
+        return snap;
+    }
+
+    pub fn snap_points(
+        &self,
+        points: &[GeoData],
+        tolerance: f64,
+        method: &DistanceMethod,
+    ) -> Vec<Snap> {
+        // Snap every point feature within tolerance (m) of a target edge, others are left out
+        return points
+            .iter()
+            .filter_map(|row| {
+                let point = *row.point()?;
+                let (target, snapped, distance) = self.snap_point(&point, tolerance, method)?;
+                Some(Snap {
+                    uuid: row.uuid.clone(),
+                    target_uuid: target.uuid.clone(),
+                    point,
+                    snapped,
+                    distance,
+                })
+            })
+            .collect();
+    }
+
+    fn envelope_lower_bound(
+        &self,
+        point: &Point,
+        method: &DistanceMethod,
+    ) -> Option<Box<dyn Fn(f64) -> f64>> {
+        // Distance (m) no feature beyond a given envelope co-ord distance can beat, if any
+        let ellipsoid = match method.ellipsoid() {
+            Some(ellipsoid) => ellipsoid,
+            None => return Some(Box::new(|dist: f64| dist)),
+        };
+        let extent = self.extent?;
+
+        // Across more than 180 degrees of longitude, co-ord distant features (e.g. over the
+        // antimeridian) can be close on the ground, so nothing can be ruled out
+        let lon_span = extent.max().x.max(point.x()) - extent.min().x.min(point.x());
+        if lon_span > 180.0 {
+            return None;
+        }
+
+        // Haversine angle is at least 2 asin(cos(max lat) sin(d / 2)) for co-ord distance d,
+        // with ground distance at least the smallest radius of curvature times the angle
+        let max_abs_lat = [extent.min().y, extent.max().y, point.y()]
+            .into_iter()
+            .fold(0.0, |max: f64, lat| max.max(lat.abs()))
+            .min(90.0);
+        let cos_lat = max_abs_lat.to_radians().cos();
+        let radius = min_radius(&ellipsoid);
+
+        return Some(Box::new(move |dist: f64| {
+            let half_angle = dist.min(180.0).to_radians() / 2.0;
+            radius * 2.0 * (cos_lat * half_angle.sin()).asin()
+        }));
+    }
+}
+
+fn envelope_entries(data: &[GeoData]) -> Vec<IndexEntry> {
+    // Bounding box of each feature with a geometry, tagged with its position in data
+    return data
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, row)| {
+            let rect = row.bounding_rect()?;
+            let envelope = Rectangle::from_corners(rect.min().into(), rect.max().into());
+            Some(GeomWithData::new(envelope, idx))
+        })
+        .collect();
+}
+
+pub fn envelope_tree(data: &[GeoData]) -> RTree<IndexEntry> {
+    // Bare R-tree over borrowed data, for one-off prefilters that don't need a GeoIndex
+    return RTree::bulk_load(envelope_entries(data));
+}
+
+pub fn locate_rect<'a>(
+    tree: &'a RTree<IndexEntry>,
+    rect: &Rect,
+) -> impl Iterator<Item = usize> + 'a {
+    // Positions of the features whose bounding box intersects rect
+    let envelope = AABB::from_corners(rect.min().into(), rect.max().into());
+    return tree
+        .locate_in_envelope_intersecting(&envelope)
+        .map(|entry| entry.data);
+}
+
+pub fn within_distance<'a>(
+    data: &'a [GeoData],
+    geometry: &Geometry,
+    distance: f64,
+    method: &DistanceMethod,
+) -> Vec<(&'a GeoData, f64)> {
+    // Unindexed within distance search, bounding boxes prefilter the exact distances
+    let rect = match search_rect(geometry, distance, method) {
+        Some(rect) => rect,
+        None => return vec![],
+    };
+
+    let candidates = data
+        .iter()
+        .filter(|row| match row.bounding_rect() {
+            Some(row_rect) => row_rect.intersects(&rect),
+            None => false,
+        })
+        .collect();
+
+    return refine_within_distance(candidates, geometry, distance, method);
+}
+
+fn refine_within_distance<'a>(
+    candidates: Vec<&'a GeoData>,
+    geometry: &Geometry,
+    distance: f64,
+    method: &DistanceMethod,
+) -> Vec<(&'a GeoData, f64)> {
+    let mut within: Vec<(&GeoData, f64)> = candidates
+        .into_iter()
+        .map(|row| (row, row.distance_to_geometry(geometry, method)))
+        .filter(|(_, dist)| *dist <= distance)
+        .collect();
+
+    within.sort_by(|a, b| a.1.total_cmp(&b.1));
+    return within;
+}
+
+pub fn search_rect(geometry: &Geometry, distance: f64, method: &DistanceMethod) -> Option<Rect> {
+    // Geometry bounding box grown by distance (m) in co-ord units, never too small
+    let rect = geometry.bounding_rect()?;
+    let (dx, dy) = match method.ellipsoid() {
+        None => (distance, distance),
+        Some(ellipsoid) => {
+            // Largest angle distance can span, then the latitude & longitude it can cover
+            let angle = (distance / min_radius(&ellipsoid)).min(std::f64::consts::PI);
+            let dy = angle.to_degrees();
+            let max_abs_lat = rect.min().y.abs().max(rect.max().y.abs()) + dy;
+            let half_lon = (angle / 2.0).sin() / max_abs_lat.min(90.0).to_radians().cos();
+            let dx = 2.0 * half_lon.asin().to_degrees();
+
+            // Co-ords wrap at the antimeridian, so a rect reaching it spans every longitude
+            let wraps = rect.min().x - dx < -180.0 || rect.max().x + dx > 180.0;
+            match max_abs_lat < 90.0 && half_lon < 1.0 && !wraps {
+                true => (dx, dy),
+                false => (360.0, dy),
+            }
+        }
+    };
+
+    let grow = Coord { x: dx, y: dy };
+    return Some(Rect::new(rect.min() - grow, rect.max() + grow));
+}
+
+fn min_radius(ellipsoid: &Ellipsoid) -> f64 {
+    // Smallest radius of curvature is the meridian at the equator, b2 / a, so no path is
+    // shorter than it times the angle between the end points' normals
+    return ellipsoid.semi_minor_axis().powi(2) / ellipsoid.semi_major_axis;
+}
+
+#[cfg(test)]
+fn example_sites(count: usize) -> Vec<GeoData> {
+    use geo::polygon;
+
+    // Grid of ~200m square sites across Scotland (lon / lat)
+    let side = (count as f64).sqrt().ceil() as usize;
+    return (0..count)
+        .map(|idx| {
+            let lon = -7.0 + 5.0 * (idx % side) as f64 / side as f64;
+            let lat = 55.0 + 3.5 * (idx / side) as f64 / side as f64;
+            GeoData::from_geometry(
+                &format!("site_{}", idx),
+                polygon![
+                    (x: lon, y: lat),
+                    (x: lon + 0.003, y: lat),
+                    (x: lon + 0.003, y: lat + 0.002),
+                    (x: lon, y: lat + 0.002),
+                    (x: lon, y: lat),
+                ],
+            )
+        })
+        .collect();
+}
+
+#[cfg(test)]
+fn brute_force_nearest<'a>(
+    data: &'a [GeoData],
+    point: &Point,
+    k: usize,
+    method: &DistanceMethod,
+) -> Vec<(&'a GeoData, f64)> {
+    let mut dists: Vec<(&GeoData, f64)> = data
+        .iter()
+        .map(|row| (row, row.distance_to_point(point, method)))
+        .collect();
+    dists.sort_by(|a, b| a.1.total_cmp(&b.1));
+    dists.truncate(k);
+    return dists;
+}
+
+#[test]
+fn test_nearest() {
+    use geo::point;
+
+    let index = GeoIndex::new(example_sites(400));
+    assert_eq!(index.len(), 400);
+
+    for method in [DistanceMethod::Haversine, DistanceMethod::Geodesic] {
+        for query in [
+            point!(x: -3.2007650172960296, y: 55.95042325369335),
+            point!(x: -6.5, y: 58.9),
+            point!(x: -1.0, y: 54.0),
+        ] {
+            let nearest = index.nearest(&query, 5, &method);
+            let expected = brute_force_nearest(&index.data, &query, 5, &method);
+
+            assert_eq!(nearest.len(), 5);
+            for (found, brute) in nearest.iter().zip(expected.iter()) {
+                assert_eq!(found.0.uuid, brute.0.uuid);
+                assert_eq!(found.1, brute.1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_nearest_far() {
+    use geo::point;
+
+    // Over the pole : the far side at 70N is closer than the same meridian at 5N
+    let polar = GeoIndex::new(vec![
+        GeoData::from_geometry("same_meridian", point!(x: -90.0, y: 5.0)),
+        GeoData::from_geometry("over_pole", point!(x: 90.0, y: 70.0)),
+    ]);
+    // Across the antimeridian : ~20km apart despite 359.8 degrees of longitude
+    let antimeridian = GeoIndex::new(vec![
+        GeoData::from_geometry("same_side", point!(x: 178.0, y: 0.0)),
+        GeoData::from_geometry("other_side", point!(x: -179.9, y: 0.0)),
+    ]);
+
+    for method in [
+        DistanceMethod::Haversine,
+        DistanceMethod::Geodesic,
+        DistanceMethod::Rhumb,
+    ] {
+        for (index, query) in [
+            (&polar, point!(x: -90.0, y: 60.0)),
+            (&antimeridian, point!(x: 179.9, y: 0.0)),
+        ] {
+            let nearest = index.nearest(&query, 1, &method);
+            let expected = brute_force_nearest(&index.data, &query, 1, &method);
+            assert_eq!(nearest, expected);
+        }
+    }
+    assert_eq!(
+        polar.nearest(&point!(x: -90.0, y: 60.0), 1, &DistanceMethod::Geodesic)[0]
+            .0
+            .uuid,
+        "over_pole"
+    );
+    assert_eq!(
+        antimeridian.nearest(&point!(x: 179.9, y: 0.0), 1, &DistanceMethod::Geodesic)[0]
+            .0
+            .uuid,
+        "other_side"
+    );
+
+    // Search rects cover long distances at high latitude
+    let found = polar.within_distance(
+        &Geometry::Point(point!(x: -90.0, y: 60.0)),
+        6_000_000.0,
+        &DistanceMethod::Geodesic,
+    );
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0.uuid, "over_pole");
+
+    let found = antimeridian.within_distance(
+        &Geometry::Point(point!(x: 179.9, y: 0.0)),
+        50_000.0,
+        &DistanceMethod::Geodesic,
+    );
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0.uuid, "other_side");
+}
+
+#[test]
+fn test_query_bbox() {
+    use geo::{Rect, coord};
+
+    let index = GeoIndex::new(example_sites(400));
+    let bbox = Rect::new(coord! { x: -4.0, y: 56.0 }, coord! { x: -3.0, y: 57.0 });
+    let found = index.query_bbox(&bbox);
+
+    let expected = index
+        .data
+        .iter()
+        .filter(|row| {
+            let rect = row.bounding_rect().unwrap();
+            rect.min().x <= bbox.max().x
+                && rect.max().x >= bbox.min().x
+                && rect.min().y <= bbox.max().y
+                && rect.max().y >= bbox.min().y
+        })
+        .count();
+
+    assert!(!found.is_empty());
+    assert_eq!(found.len(), expected);
+}
+
+#[test]
+fn test_within_distance() {
+    use geo::point;
+
+    let index = GeoIndex::new(example_sites(400));
+    let edinburgh = point!(x: -3.2007650172960296, y: 55.95042325369335);
+    let method = DistanceMethod::Geodesic;
+
+    // Point query matches the brute force distances
+    let found = index.within_distance(&Geometry::Point(edinburgh), 20_000.0, &method);
+    let expected: Vec<(&GeoData, f64)> = brute_force_nearest(&index.data, &edinburgh, 400, &method)
+        .into_iter()
+        .filter(|(_, dist)| *dist <= 20_000.0)
+        .collect();
+
+    assert!(!found.is_empty());
+    assert_eq!(found.len(), expected.len());
+    assert!(found.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    assert_eq!(
+        found,
+        within_distance(&index.data, &Geometry::Point(edinburgh), 20_000.0, &method)
+    );
+
+    // Polygon query includes itself at zero distance
+    let site = index.data[210].polygon().unwrap().clone();
+    let found = index.within_distance(&Geometry::Polygon(site), 30_000.0, &method);
+    assert_eq!(found[0].0.uuid, "site_210");
+    assert_eq!(found[0].1, 0.0);
+    assert!(found.iter().all(|(_, dist)| *dist <= 30_000.0));
+}
+
+#[test]
+fn test_snap_points() {
+    use geo::point;
+
+    let index = GeoIndex::new(example_sites(400));
+    let method = DistanceMethod::Geodesic;
+
+    // Stations just off (and just inside) site boundaries, and one far from any site
+    let site = index.data[210].polygon().unwrap().clone();
+    let corner = site.exterior().0[0];
+    let stations = vec![
+        GeoData::from_geometry("outside", point!(x: corner.x + 0.001, y: corner.y - 0.0001)),
+        GeoData::from_geometry("inside", point!(x: corner.x + 0.001, y: corner.y + 0.0001)),
+        GeoData::from_geometry("far", point!(x: corner.x + 0.001, y: corner.y - 0.01)),
+    ];
+
+    let snaps = index.snap_points(&stations, 25.0, &method);
+    assert_eq!(snaps.len(), 2);
+
+    for snap in snaps {
+        assert_eq!(snap.target_uuid, "site_210");
+        assert!(snap.distance > 10.0 && snap.distance < 12.0);
+        // Geodesic edges bow slightly poleward of the parallel
+        assert!((snap.snapped.y() - corner.y).abs() < 1e-6);
+        assert!(
+            (dist::point_distance(&snap.point, &snap.snapped, &method) - snap.distance).abs()
+                < 1e-9
+        );
+    }
+}
+
+// Benchmark : cargo test --release bench_nearest -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_nearest() {
+    use geo::point;
+    use std::time::Instant;
+
+    let index = GeoIndex::new(example_sites(2_000));
+    let queries: Vec<Point> = (0..100)
+        .map(|idx| {
+            let lon = -7.0 + 5.0 * (idx % 10) as f64 / 10.0;
+            let lat = 55.0 + 3.5 * (idx / 10) as f64 / 10.0;
+            point!(x: lon, y: lat)
+        })
+        .collect();
+    let method = DistanceMethod::Haversine;
+
+    let start = Instant::now();
+    for query in &queries {
+        index.nearest(query, 1, &method);
+    }
+    let indexed = start.elapsed();
+
+    let start = Instant::now();
+    for query in &queries {
+        brute_force_nearest(&index.data, query, 1, &method);
+    }
+    let brute_force = start.elapsed();
+
+    println!(
+        "Nearest neighbour : {} sites, {} queries",
+        index.len(),
+        queries.len()
+    );
+    println!("R-tree : {:?}", indexed);
+    println!("Brute force : {:?}", brute_force);
+}
@@ -1,9 +1,14 @@
 // Geospatial Modelling
-#![allow(dead_code, unused_imports, unused_variables)]
+#![allow(dead_code, unused_imports, unused_variables, clippy::needless_return)]
+mod buffer;
 mod coord;
 mod dist;
 mod geodb;
+mod index;
+mod measure;
+mod network;
 mod utils;
+mod writer;
 
 fn main() {
     let mut geofiles = std::collections::HashMap::new();
@@ -41,16 +46,19 @@ fn main() {
     );
 
     let geo_db = geodb::GeoDB {
-        url: geofiles["bathing_waters_points"].to_string(),
-        zipfile: Some("SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string()),
+        source: geodb::GeoSource::Url {
+            url: geofiles["bathing_waters_points"].to_string(),
+            zipfile: Some("SEPA_BATHING_WATER_POINTS_BNG_gpkg.zip".to_string()),
+        },
         db: "SEPA_BATHING_WATER_POINTS_BNG.gpkg".to_string(),
-        table: "SEPA_BATHING_WATER_POINTS_BNG".to_string(),
-        crs: crs_definitions::EPSG_27700,
+        table: Some("SEPA_BATHING_WATER_POINTS_BNG".to_string()),
+        crs: Some(crs_definitions::EPSG_27700),
+        uuid_col: Some("bw_url".to_string()),
         uuid_col_idx: 8,
         geometry_col_idx: 1,
     };
 
-    let geo_db_data = geo_db.extract();
+    let geo_db_data = geo_db.extract().expect("failed to extract layer");
     println!("{:#?}", geo_db_data);
 }
 
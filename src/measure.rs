@@ -0,0 +1,277 @@
+// Geospatial Area, Perimeter & Length Measurements
+use crate::dist::{DistanceMethod, closest_point_on_edge, point_at_ratio, point_distance};
+use geo::{Area, Line, LineString, MultiLineString, MultiPolygon, Point, Polygon};
+use geographiclib_rs::{PolygonArea, Winding};
+
+pub fn polygon_area(polygon: &Polygon, method: &DistanceMethod) -> f64 {
+    // Area (m2) of the exterior ring less any holes
+    let holes: f64 = polygon
+        .interiors()
+        .iter()
+        .map(|ring| ring_area(ring, method))
+        .sum();
+
+    return ring_area(polygon.exterior(), method) - holes;
+}
+
+pub fn multipolygon_area(multipolygon: &MultiPolygon, method: &DistanceMethod) -> f64 {
+    return multipolygon
+        .iter()
+        .map(|polygon| polygon_area(polygon, method))
+        .sum();
+}
+
+pub fn polygon_perimeter(polygon: &Polygon, method: &DistanceMethod) -> f64 {
+    // Boundary length (m) including the edges of any holes
+    return std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .map(|ring| linestring_length(ring, method))
+        .sum();
+}
+
+pub fn multipolygon_perimeter(multipolygon: &MultiPolygon, method: &DistanceMethod) -> f64 {
+    return multipolygon
+        .iter()
+        .map(|polygon| polygon_perimeter(polygon, method))
+        .sum();
+}
+
+fn ring_area(ring: &LineString, method: &DistanceMethod) -> f64 {
+    let ellipsoid = match method.ellipsoid() {
+        Some(ellipsoid) => ellipsoid,
+        None => return Polygon::new(ring.clone(), vec![]).unsigned_area(),
+    };
+
+    let geodesic = geographiclib_rs::Geodesic::new(ellipsoid.semi_major_axis, ellipsoid.flattening);
+    let mut area = PolygonArea::new(&geodesic, Winding::CounterClockwise);
+
+    // Closing co-ord is implied by PolygonArea
+    let coords = &ring.0;
+    let open_len = match ring.is_closed() {
+        true => coords.len().saturating_sub(1),
+        false => coords.len(),
+    };
+    for coord in &coords[..open_len] {
+        area.add_point(coord.y, coord.x);
+    }
+
+    // Signed result avoids the 'rest of the earth' area for clockwise rings
+    let (_, signed_area, _) = area.compute(true);
+    return signed_area.abs();
+}
+
+pub fn linestring_length(linestring: &LineString, method: &DistanceMethod) -> f64 {
+    return linestring
+        .lines()
+        .map(|line| line_length(&line, method))
+        .sum();
+}
+
+pub fn multilinestring_length(multilinestring: &MultiLineString, method: &DistanceMethod) -> f64 {
+    return multilinestring
+        .iter()
+        .map(|linestring| linestring_length(linestring, method))
+        .sum();
+}
+
+pub fn locate_point(
+    linestring: &LineString,
+    point: &Point,
+    method: &DistanceMethod,
+) -> Option<f64> {
+    // Measure (m along the line) of the closest point on the line to point
+    let mut measure = 0.0;
+    let mut closest: Option<(f64, f64)> = None;
+
+    for line in linestring.lines() {
+        let on_line = closest_point_on_edge(point, &line, method);
+        let dist = point_distance(point, &on_line, method);
+
+        if closest.is_none_or(|(_, best)| dist < best) {
+            let along = point_distance(&line.start.into(), &on_line, method);
+            closest = Some((measure + along, dist));
+        }
+        measure += line_length(&line, method);
+    }
+
+    // Single co-ord lines are located at their start
+    if closest.is_none() && !linestring.0.is_empty() {
+        return Some(0.0);
+    }
+    return closest.map(|(measure, _)| measure);
+}
+
+pub fn interpolate_point(
+    linestring: &LineString,
+    measure: f64,
+    method: &DistanceMethod,
+) -> Option<Point> {
+    // Point at measure (m) along the line, clamped to the line ends
+    let mut start_measure = 0.0;
+
+    for line in linestring.lines() {
+        let length = line_length(&line, method);
+        if measure <= start_measure + length && length > 0.0 {
+            let ratio = ((measure - start_measure) / length).max(0.0);
+            return Some(point_at_ratio(
+                &line.start.into(),
+                &line.end.into(),
+                ratio,
+                method,
+            ));
+        }
+        start_measure += length;
+    }
+
+    return linestring.0.last().map(|coord| Point(*coord));
+}
+
+pub fn sub_linestring(
+    linestring: &LineString,
+    start_measure: f64,
+    end_measure: f64,
+    method: &DistanceMethod,
+) -> LineString {
+    // Section of the line between two measures (m), reversed when end is before start
+    if end_measure < start_measure {
+        let mut reversed = sub_linestring(linestring, end_measure, start_measure, method);
+        reversed.0.reverse();
+        return reversed;
+    }
+
+    let (start, end) = match (
+        interpolate_point(linestring, start_measure, method),
+        interpolate_point(linestring, end_measure, method),
+    ) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return LineString::new(vec![]),
+    };
+
+    // Keep the vertices strictly between the two measures
+    let mut coords = vec![start.0];
+    let mut measure = 0.0;
+    for line in linestring.lines() {
+        measure += line_length(&line, method);
+        if measure > start_measure && measure < end_measure {
+            coords.push(line.end);
+        }
+    }
+    coords.push(end.0);
+
+    return LineString::new(coords);
+}
+
+fn line_length(line: &Line, method: &DistanceMethod) -> f64 {
+    return point_distance(&line.start.into(), &line.end.into(), method);
+}
+
+#[test]
+fn test_polygon_area() {
+    use crate::coord;
+    use crs_definitions as crs_refs;
+    use geo::polygon;
+
+    // 1km square in BNG with a 100m square hole
+    let polygon: Polygon<f64> = polygon!(
+        exterior: [
+            (x: 325000.0, y: 673000.0),
+            (x: 326000.0, y: 673000.0),
+            (x: 326000.0, y: 674000.0),
+            (x: 325000.0, y: 674000.0),
+            (x: 325000.0, y: 673000.0),
+        ],
+        interiors: [
+            [
+                (x: 325400.0, y: 673400.0),
+                (x: 325400.0, y: 673500.0),
+                (x: 325500.0, y: 673500.0),
+                (x: 325500.0, y: 673400.0),
+                (x: 325400.0, y: 673400.0),
+            ],
+        ],
+    );
+
+    assert_eq!(polygon_area(&polygon, &DistanceMethod::Planar), 990_000.0);
+    assert_eq!(
+        polygon_perimeter(&polygon, &DistanceMethod::Planar),
+        4_400.0
+    );
+
+    // Geodesic on lon / lat agrees with BNG within its scale factor
+    let polygon_tf = coord::update_poly_crs(&polygon, &crs_refs::EPSG_27700, &crs_refs::EPSG_4326);
+    assert_eq!(polygon_tf.interiors().len(), 1);
+
+    let area = polygon_area(&polygon_tf, &DistanceMethod::Geodesic);
+    let perimeter = polygon_perimeter(&polygon_tf, &DistanceMethod::Geodesic);
+    assert!(100.0 * ((area - 990_000.0) / 990_000.0).abs() < 0.2);
+    assert!(100.0 * ((perimeter - 4_400.0) / 4_400.0).abs() < 0.1);
+
+    // Clockwise rings give the same (positive) area
+    let mut reversed = polygon_tf.clone();
+    reversed.exterior_mut(|ring| ring.0.reverse());
+    assert!((polygon_area(&reversed, &DistanceMethod::Geodesic) - area).abs() < 0.001);
+
+    // Multipolygon sums its parts
+    let multipolygon = MultiPolygon::new(vec![polygon_tf.clone(), polygon_tf.clone()]);
+    assert!(
+        (multipolygon_area(&multipolygon, &DistanceMethod::Geodesic) - 2.0 * area).abs() < 0.001
+    );
+}
+
+#[test]
+fn test_linestring_length() {
+    use geo::line_string;
+
+    let river: LineString = line_string![
+        (x: -3.20, y: 55.95),
+        (x: -3.25, y: 55.97),
+        (x: -3.31, y: 55.98),
+        (x: -3.40, y: 56.00),
+    ];
+    let method = DistanceMethod::Geodesic;
+
+    let length = linestring_length(&river, &method);
+    let expected: f64 = river
+        .lines()
+        .map(|line| point_distance(&line.start.into(), &line.end.into(), &method))
+        .sum();
+    assert_eq!(length, expected);
+
+    let multi = MultiLineString::new(vec![river.clone(), river]);
+    assert_eq!(multilinestring_length(&multi, &method), 2.0 * length);
+}
+
+#[test]
+fn test_linear_referencing() {
+    use geo::{line_string, point};
+
+    let river: LineString = line_string![
+        (x: -3.20, y: 55.95),
+        (x: -3.25, y: 55.97),
+        (x: -3.31, y: 55.98),
+        (x: -3.40, y: 56.00),
+    ];
+    let method = DistanceMethod::Geodesic;
+    let length = linestring_length(&river, &method);
+
+    // Interpolated points locate back to their measure
+    for measure in [0.0, 1_000.0, 5_500.0, length] {
+        let point = interpolate_point(&river, measure, &method).unwrap();
+        let located = locate_point(&river, &point, &method).unwrap();
+        assert!((located - measure).abs() < 0.01);
+    }
+
+    // Measures beyond the line clamp to its ends
+    assert_eq!(
+        interpolate_point(&river, length + 100.0, &method),
+        Some(point!(x: -3.40, y: 56.00))
+    );
+
+    // Sub-lines run between the two measures, either way round
+    let section = sub_linestring(&river, 1_000.0, 9_000.0, &method);
+    assert!((linestring_length(&section, &method) - 8_000.0).abs() < 0.01);
+    assert_eq!(section.0.len(), 4);
+
+    let reversed = sub_linestring(&river, 9_000.0, 1_000.0, &method);
+    assert_eq!(reversed.0.first(), section.0.last());
+}
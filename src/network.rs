@@ -0,0 +1,387 @@
+// River Network Distances
+use crate::dist::{DistanceMethod, closest_point_on_edge, point_distance};
+use crate::index::search_rect;
+use geo::{Geometry, Line, MultiLineString, Point};
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{AABB, RTree};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+type NodeEntry = GeomWithData<[f64; 2], usize>;
+type SegmentEntry = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkPosition {
+    pub segment: usize,
+    pub point: Point,
+    pub along: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment {
+    from: usize,
+    to: usize,
+    length: f64,
+}
+
+pub struct RiverNetwork {
+    pub nodes: Vec<Point>,
+    segments: Vec<Segment>,
+    downstream: Vec<Vec<usize>>,
+    upstream: Vec<Vec<usize>>,
+    segment_tree: RTree<SegmentEntry>,
+    method: DistanceMethod,
+}
+
+impl RiverNetwork {
+    pub fn new(rivers: &MultiLineString, tolerance: f64, method: DistanceMethod) -> RiverNetwork {
+        // Every vertex is a node, vertices within tolerance (m) of each other share one
+        // Lines are taken as digitised in the direction of flow
+        let mut nodes: Vec<Point> = vec![];
+        let mut node_tree: RTree<NodeEntry> = RTree::new();
+        let mut segments: Vec<Segment> = vec![];
+
+        for river in rivers {
+            let mut previous: Option<usize> = None;
+            for coord in river.coords() {
+                let point = Point(*coord);
+                let node = match find_node(&node_tree, &nodes, &point, tolerance, &method) {
+                    Some(node) => node,
+                    None => {
+                        node_tree.insert(GeomWithData::new([point.x(), point.y()], nodes.len()));
+                        nodes.push(point);
+                        nodes.len() - 1
+                    }
+                };
+
+                if let Some(from) = previous
+                    && from != node
+                {
+                    let length = point_distance(&nodes[from], &nodes[node], &method);
+                    segments.push(Segment {
+                        from,
+                        to: node,
+                        length,
+                    });
+                }
+                previous = Some(node);
+            }
+        }
+
+        let mut downstream: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+        let mut upstream: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+        for (idx, segment) in segments.iter().enumerate() {
+            downstream[segment.from].push(idx);
+            upstream[segment.to].push(idx);
+        }
+
+        let segment_tree = RTree::bulk_load(
+            segments
+                .iter()
+                .enumerate()
+                .map(|(idx, segment)| {
+                    let (from, to) = (nodes[segment.from], nodes[segment.to]);
+                    let envelope = Rectangle::from_corners([from.x(), from.y()], [to.x(), to.y()]);
+                    GeomWithData::new(envelope, idx)
+                })
+                .collect(),
+        );
+
+        return RiverNetwork {
+            nodes,
+            segments,
+            downstream,
+            upstream,
+            segment_tree,
+            method,
+        };
+    }
+
+    pub fn segment_count(&self) -> usize {
+        return self.segments.len();
+    }
+
+    pub fn snap(&self, point: &Point, tolerance: f64) -> Option<NetworkPosition> {
+        // Closest position on the network within tolerance (m) of point
+        let rect = search_rect(&Geometry::Point(*point), tolerance, &self.method)?;
+        let envelope =
+            AABB::from_corners([rect.min().x, rect.min().y], [rect.max().x, rect.max().y]);
+        let mut closest: Option<(NetworkPosition, f64)> = None;
+
+        for entry in self.segment_tree.locate_in_envelope_intersecting(&envelope) {
+            let segment = &self.segments[entry.data];
+            let line = Line::new(self.nodes[segment.from].0, self.nodes[segment.to].0);
+            let on_line = closest_point_on_edge(point, &line, &self.method);
+            let dist = point_distance(point, &on_line, &self.method);
+
+            if dist <= tolerance && closest.is_none_or(|(_, best)| dist < best) {
+                let along = point_distance(&self.nodes[segment.from], &on_line, &self.method);
+                let position = NetworkPosition {
+                    segment: entry.data,
+                    point: on_line,
+                    along: along.min(segment.length),
+                };
+                closest = Some((position, dist));
+            }
+        }
+
+        return closest.map(|(position, _)| position);
+    }
+
+    pub fn distance(&self, from: &NetworkPosition, to: &NetworkPosition) -> Option<f64> {
+        // Shortest path (m) along the network in either direction of flow
+        return self.shortest_path(from, to, false);
+    }
+
+    pub fn downstream_distance(&self, from: &NetworkPosition, to: &NetworkPosition) -> Option<f64> {
+        // Distance (m) travelled with the flow from from to to, None when to isn't downstream
+        return self.shortest_path(from, to, true);
+    }
+
+    pub fn upstream_distance(&self, from: &NetworkPosition, to: &NetworkPosition) -> Option<f64> {
+        return self.shortest_path(to, from, true);
+    }
+
+    pub fn is_downstream(&self, from: &NetworkPosition, to: &NetworkPosition) -> bool {
+        return self.downstream_distance(from, to).is_some();
+    }
+
+    pub fn point_distance(&self, from: &Point, to: &Point, tolerance: f64) -> Option<f64> {
+        // Network distance (m) between two points snapped within tolerance (m)
+        let from = self.snap(from, tolerance)?;
+        let to = self.snap(to, tolerance)?;
+        return self.distance(&from, &to);
+    }
+
+    fn shortest_path(
+        &self,
+        from: &NetworkPosition,
+        to: &NetworkPosition,
+        directed: bool,
+    ) -> Option<f64> {
+        let from_segment = self.segments[from.segment];
+        let to_segment = self.segments[to.segment];
+
+        // Both on the same segment can be a direct hop
+        let mut best = f64::INFINITY;
+        if from.segment == to.segment && (!directed || to.along >= from.along) {
+            best = (to.along - from.along).abs();
+        }
+
+        // Dijkstra from the ends of the start segment
+        let mut dists: Vec<f64> = vec![f64::INFINITY; self.nodes.len()];
+        let mut heap = BinaryHeap::new();
+        let mut starts = vec![(from_segment.to, from_segment.length - from.along)];
+        if !directed {
+            starts.push((from_segment.from, from.along));
+        }
+        for (node, dist) in starts {
+            if dist < dists[node] {
+                dists[node] = dist;
+                heap.push(State { dist, node });
+            }
+        }
+
+        while let Some(State { dist, node }) = heap.pop() {
+            if dist > dists[node] || dist >= best {
+                continue;
+            }
+
+            let mut next: Vec<(usize, f64)> = self.downstream[node]
+                .iter()
+                .map(|idx| (self.segments[*idx].to, self.segments[*idx].length))
+                .collect();
+            if !directed {
+                next.extend(
+                    self.upstream[node]
+                        .iter()
+                        .map(|idx| (self.segments[*idx].from, self.segments[*idx].length)),
+                );
+            }
+
+            for (next_node, length) in next {
+                let next_dist = dist + length;
+                if next_dist < dists[next_node] {
+                    dists[next_node] = next_dist;
+                    heap.push(State {
+                        dist: next_dist,
+                        node: next_node,
+                    });
+                }
+            }
+        }
+
+        // Finish along the target segment
+        best = best.min(dists[to_segment.from] + to.along);
+        if !directed {
+            best = best.min(dists[to_segment.to] + (to_segment.length - to.along));
+        }
+
+        match best.is_finite() {
+            true => return Some(best),
+            false => return None,
+        }
+    }
+}
+
+fn find_node(
+    node_tree: &RTree<NodeEntry>,
+    nodes: &[Point],
+    point: &Point,
+    tolerance: f64,
+    method: &DistanceMethod,
+) -> Option<usize> {
+    let rect = search_rect(&Geometry::Point(*point), tolerance, method)?;
+    let envelope = AABB::from_corners([rect.min().x, rect.min().y], [rect.max().x, rect.max().y]);
+
+    return node_tree
+        .locate_in_envelope(&envelope)
+        .map(|entry| {
+            (
+                entry.data,
+                point_distance(point, &nodes[entry.data], method),
+            )
+        })
+        .filter(|(_, dist)| *dist <= tolerance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(node, _)| node);
+}
+
+// Min-heap ordering on distance for Dijkstra
+#[derive(Debug, PartialEq)]
+struct State {
+    dist: f64,
+    node: usize,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return other.dist.total_cmp(&self.dist);
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+#[cfg(test)]
+fn example_rivers() -> MultiLineString {
+    use geo::line_string;
+
+    // Two tributaries joining a main stem, all digitised downstream (BNG metres)
+    // The north tributary stops 0.5m short of the confluence
+    return MultiLineString::new(vec![
+        line_string![(x: 320000.0, y: 670000.0), (x: 321000.0, y: 670000.0), (x: 322000.0, y: 670000.0)],
+        line_string![(x: 322000.0, y: 672000.0), (x: 322000.0, y: 670000.5)],
+        line_string![(x: 322000.0, y: 670000.0), (x: 325000.0, y: 670000.0)],
+    ]);
+}
+
+#[test]
+fn test_network_distance() {
+    use geo::point;
+
+    let network = RiverNetwork::new(&example_rivers(), 1.0, DistanceMethod::Planar);
+    assert_eq!(network.nodes.len(), 5);
+    assert_eq!(network.segment_count(), 4);
+
+    let west = network
+        .snap(&point!(x: 320500.0, y: 670010.0), 25.0)
+        .unwrap();
+    let north = network
+        .snap(&point!(x: 321990.0, y: 671000.0), 25.0)
+        .unwrap();
+    let east = network
+        .snap(&point!(x: 324000.0, y: 669990.0), 25.0)
+        .unwrap();
+
+    // Along the network, not straight line
+    assert!((network.distance(&west, &north).unwrap() - (1_500.0 + 1_000.0)).abs() < 0.001);
+    assert!((network.distance(&west, &east).unwrap() - (1_500.0 + 2_000.0)).abs() < 0.001);
+    assert!((network.distance(&east, &west).unwrap() - (3_500.0)).abs() < 0.001);
+
+    // Too far from any river
+    assert!(
+        network
+            .snap(&point!(x: 323000.0, y: 671000.0), 25.0)
+            .is_none()
+    );
+    assert!(
+        network
+            .point_distance(
+                &point!(x: 320500.0, y: 670010.0),
+                &point!(x: 324000.0, y: 669990.0),
+                25.0
+            )
+            .is_some()
+    );
+}
+
+#[test]
+fn test_network_reachability() {
+    use geo::point;
+
+    let network = RiverNetwork::new(&example_rivers(), 1.0, DistanceMethod::Planar);
+    let west = network
+        .snap(&point!(x: 320500.0, y: 670000.0), 25.0)
+        .unwrap();
+    let north = network
+        .snap(&point!(x: 322000.0, y: 671000.0), 25.0)
+        .unwrap();
+    let east = network
+        .snap(&point!(x: 324000.0, y: 670000.0), 25.0)
+        .unwrap();
+
+    // Tributaries flow into the main stem
+    assert!((network.downstream_distance(&west, &east).unwrap() - (3_500.0)).abs() < 0.001);
+    assert!((network.downstream_distance(&north, &east).unwrap() - (3_000.0)).abs() < 0.001);
+    assert!(network.is_downstream(&north, &east));
+
+    // Not against the flow, nor across from one tributary to the other
+    assert!(!network.is_downstream(&east, &west));
+    assert!(!network.is_downstream(&west, &north));
+    assert!((network.upstream_distance(&east, &west).unwrap() - (3_500.0)).abs() < 0.001);
+
+    // Same segment respects direction
+    let west_lower = network
+        .snap(&point!(x: 320800.0, y: 670000.0), 25.0)
+        .unwrap();
+    assert!((network.downstream_distance(&west, &west_lower).unwrap() - (300.0)).abs() < 0.001);
+    assert!(!network.is_downstream(&west_lower, &west));
+}
+
+#[test]
+fn test_network_geodesic() {
+    use crate::coord;
+    use crs_definitions as crs_refs;
+    use geo::{MapCoords, point};
+
+    // Same network in lon / lat with geodesic edge weights
+    let rivers = example_rivers().map_coords(|coord| {
+        coord::update_point_crs(Point(coord), &crs_refs::EPSG_27700, &crs_refs::EPSG_4326).0
+    });
+    let network = RiverNetwork::new(&rivers, 1.0, DistanceMethod::Geodesic);
+    assert_eq!(network.nodes.len(), 5);
+
+    let to_lon_lat = |x: f64, y: f64| {
+        coord::update_point_crs(
+            point!(x: x, y: y),
+            &crs_refs::EPSG_27700,
+            &crs_refs::EPSG_4326,
+        )
+    };
+    let dist = network
+        .point_distance(
+            &to_lon_lat(320500.0, 670000.0),
+            &to_lon_lat(324000.0, 670000.0),
+            1.0,
+        )
+        .unwrap();
+
+    // BNG scale factor keeps grid & ground distance within 0.1%
+    assert!(100.0 * ((dist - 3_500.0) / 3_500.0).abs() < 0.1);
+}
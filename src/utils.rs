@@ -10,10 +10,9 @@ pub fn unzip(src: &str, dest: &str) {
     let mut archive = ZipArchive::new(file).expect("Failed to access zipfile");
 
     for i in 0..archive.len() {
-        let mut arch_file = archive.by_index(i).expect(&format!(
-            "Failed to access zipfile internal item at index {}",
-            i
-        ));
+        let mut arch_file = archive
+            .by_index(i)
+            .unwrap_or_else(|_| panic!("Failed to access zipfile internal item at index {}", i));
 
         let path_out = match arch_file.enclosed_name() {
             Some(path) => path,
@@ -22,18 +21,18 @@ pub fn unzip(src: &str, dest: &str) {
 
         if arch_file.is_dir() {
             fs::create_dir_all(&path_out)
-                .expect(&format!("Failed to create new directory at index {}", i));
+                .unwrap_or_else(|_| panic!("Failed to create new directory at index {}", i));
         } else {
-            if let Some(p) = path_out.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(p).expect("Failed to create new subdir");
-                }
+            if let Some(p) = path_out.parent()
+                && !p.exists()
+            {
+                fs::create_dir_all(p).expect("Failed to create new subdir");
             }
-            let full_path = PathBuf::from(String::from(format!(
+            let full_path = PathBuf::from(format!(
                 "{}/{}",
                 dest,
                 path_out.to_str().expect("Failed to convert to str")
-            )));
+            ));
             let mut file_out = fs::File::create(&full_path).expect("Failed to create new file");
 
             io::copy(&mut arch_file, &mut file_out).expect("Failed to write to new file");
@@ -68,5 +67,5 @@ fn test_unzip() {
 
     // confirm file now exists
     let result = fs::exists(dest_file).expect("Error checking if filepath exists");
-    assert_eq!(true, result);
+    assert!(result);
 }
@@ -0,0 +1,654 @@
+// GeoPackage Writer
+use crate::geodb::{GeoData, PropertyValue, quote_ident};
+use geo::{BoundingRect, Geometry, Rect};
+use geozero::{CoordDimensions, ToWkb};
+use rusqlite::types::Value;
+use rusqlite::{Connection, params};
+use std::collections::BTreeMap;
+
+// 'GPKG' application id & GeoPackage 1.4 version
+const GPKG_APPLICATION_ID: i32 = 0x4750_4B47;
+const GPKG_USER_VERSION: i32 = 10400;
+
+#[derive(Debug, PartialEq)]
+pub struct GeoWriter {
+    pub path: String,
+    pub table: String,
+    pub crs: crs_definitions::Def,
+    pub geometry_col: String,
+    // Column to hold GeoData.uuid, None when it's already one of the properties
+    pub uuid_col: Option<String>,
+    pub spatial_index: bool,
+}
+
+impl GeoWriter {
+    pub fn write(&self, data: &[GeoData]) -> rusqlite::Result<usize> {
+        // Adds the features as a new layer, creating the gpkg & its metadata tables if req'd
+        let mut conn = Connection::open(&self.path)?;
+        let tx = conn.transaction()?;
+        create_metadata_tables(&tx)?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO gpkg_spatial_ref_sys
+            (srs_name, srs_id, organization, organization_coordsys_id, definition)
+            VALUES (?1, ?2, 'EPSG', ?2, ?3)",
+            params![srs_name(&self.crs), self.crs.code, self.crs.wkt],
+        )?;
+
+        // Attribute columns & their declared types, skipping any that clash with fid / geometry
+        let geometries: Vec<Option<Geometry>> = data
+            .iter()
+            .map(|row| row.geometry.as_ref().map(writable_geometry))
+            .collect();
+        let columns = self.attribute_columns(data);
+
+        // Names come from the input data, so are always quoted
+        let mut column_defs = vec![
+            "\"fid\" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL".to_string(),
+            format!(
+                "{} {}",
+                quote_ident(&self.geometry_col),
+                geometry_type_name(&geometries)
+            ),
+        ];
+        if let Some(uuid_col) = &self.uuid_col {
+            column_defs.push(format!("{} TEXT", quote_ident(uuid_col)));
+        }
+        for (name, decl_type) in &columns {
+            column_defs.push(format!("{} {}", quote_ident(name), decl_type));
+        }
+        tx.execute(
+            &format!(
+                "CREATE TABLE {} ({})",
+                quote_ident(&self.table),
+                column_defs.join(", ")
+            ),
+            [],
+        )?;
+
+        // Features & their envelopes
+        let mut insert_cols = vec![quote_ident(&self.geometry_col)];
+        if let Some(uuid_col) = &self.uuid_col {
+            insert_cols.push(quote_ident(uuid_col));
+        }
+        insert_cols.extend(columns.keys().map(|name| quote_ident(name)));
+        let placeholders: Vec<String> = (1..=insert_cols.len())
+            .map(|idx| format!("?{}", idx))
+            .collect();
+
+        let mut extent: Option<Rect> = None;
+        let mut envelopes: Vec<(i64, Rect)> = vec![];
+        {
+            let mut engine = tx.prepare(&format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quote_ident(&self.table),
+                insert_cols.join(", "),
+                placeholders.join(", ")
+            ))?;
+
+            for (row, geometry) in data.iter().zip(&geometries) {
+                let rect = geometry
+                    .as_ref()
+                    .and_then(|geometry| geometry.bounding_rect());
+                let mut values: Vec<Value> = vec![match geometry {
+                    Some(geometry) => Value::Blob(gpkg_wkb(geometry, rect, self.crs.code)),
+                    None => Value::Null,
+                }];
+                if self.uuid_col.is_some() {
+                    values.push(Value::Text(row.uuid.clone()));
+                }
+                for name in columns.keys() {
+                    values.push(match row.property(name) {
+                        Some(value) => value.to_value(),
+                        None => Value::Null,
+                    });
+                }
+
+                engine.execute(rusqlite::params_from_iter(values))?;
+                if let Some(rect) = rect {
+                    envelopes.push((tx.last_insert_rowid(), rect));
+                    extent = Some(match extent {
+                        Some(extent) => extend_rect(extent, rect),
+                        None => rect,
+                    });
+                }
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO gpkg_contents
+            (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id)
+            VALUES (?1, 'features', ?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                self.table,
+                extent.map(|rect| rect.min().x),
+                extent.map(|rect| rect.min().y),
+                extent.map(|rect| rect.max().x),
+                extent.map(|rect| rect.max().y),
+                self.crs.code
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO gpkg_geometry_columns
+            (table_name, column_name, geometry_type_name, srs_id, z, m)
+            VALUES (?1, ?2, ?3, ?4, 0, 0)",
+            params![
+                self.table,
+                self.geometry_col,
+                geometry_type_name(&geometries),
+                self.crs.code
+            ],
+        )?;
+
+        if self.spatial_index {
+            self.create_spatial_index(&tx, &envelopes)?;
+        }
+
+        tx.commit()?;
+        return Ok(data.len());
+    }
+
+    fn attribute_columns(&self, data: &[GeoData]) -> BTreeMap<String, String> {
+        // Declared type from the non null values of each property, null only columns as TEXT
+        let reserved: Vec<&str> = ["fid", &self.geometry_col]
+            .into_iter()
+            .chain(self.uuid_col.as_deref())
+            .collect();
+        let mut columns: BTreeMap<String, Option<&str>> = BTreeMap::new();
+
+        for row in data {
+            for (name, value) in &row.properties {
+                if reserved.iter().any(|col| col.eq_ignore_ascii_case(name)) {
+                    continue;
+                }
+                let decl_type = match value {
+                    PropertyValue::Null => None,
+                    PropertyValue::Integer(_) => Some("INTEGER"),
+                    PropertyValue::Real(_) => Some("REAL"),
+                    PropertyValue::Text(_) => Some("TEXT"),
+                    PropertyValue::Blob(_) => Some("BLOB"),
+                    PropertyValue::Date(date) if date.len() > 10 => Some("DATETIME"),
+                    PropertyValue::Date(_) => Some("DATE"),
+                };
+                let column = columns.entry(name.clone()).or_insert(None);
+                match (*column, decl_type) {
+                    (_, None) => (),
+                    (None, decl_type) => *column = decl_type,
+                    (Some(current), Some(decl_type)) if current == decl_type => (),
+                    // Mixed integer / real columns widen to real, dates to date times
+                    (Some("INTEGER"), Some("REAL")) | (Some("REAL"), Some("INTEGER")) => {
+                        *column = Some("REAL");
+                    }
+                    (Some("DATE"), Some("DATETIME")) | (Some("DATETIME"), Some("DATE")) => {
+                        *column = Some("DATETIME");
+                    }
+                    // Anything else widens to text, rather than left to SQLite type affinity
+                    _ => *column = Some("TEXT"),
+                }
+            }
+        }
+
+        return columns
+            .into_iter()
+            .map(|(name, decl_type)| (name, decl_type.unwrap_or("TEXT").to_string()))
+            .collect();
+    }
+
+    fn create_spatial_index(
+        &self,
+        conn: &Connection,
+        envelopes: &[(i64, Rect)],
+    ) -> rusqlite::Result<()> {
+        // gpkg_rtree_index extension : RTree of feature envelopes & the triggers keeping it in step
+        let rtree = format!("rtree_{}_{}", self.table, self.geometry_col);
+        let tree = quote_ident(&rtree);
+        conn.execute(
+            &format!(
+                "CREATE VIRTUAL TABLE {} USING rtree(id, minx, maxx, miny, maxy)",
+                tree
+            ),
+            [],
+        )?;
+        {
+            let mut engine =
+                conn.prepare(&format!("INSERT INTO {} VALUES (?1, ?2, ?3, ?4, ?5)", tree))?;
+            for (fid, rect) in envelopes {
+                engine.execute(params![
+                    fid,
+                    rect.min().x,
+                    rect.max().x,
+                    rect.min().y,
+                    rect.max().y
+                ])?;
+            }
+        }
+
+        // Triggers keep the index in step with later edits, via the ST_ functions GIS clients register
+        let (table, geom) = (quote_ident(&self.table), quote_ident(&self.geometry_col));
+        let trigger_name = |suffix: &str| quote_ident(&format!("{}_{}", rtree, suffix));
+        let (insert, delete) = (trigger_name("insert"), trigger_name("delete"));
+        let (update1, update2) = (trigger_name("update1"), trigger_name("update2"));
+        let (update3, update4) = (trigger_name("update3"), trigger_name("update4"));
+        let envelope = format!(
+            "NEW.\"fid\", ST_MinX(NEW.{geom}), ST_MaxX(NEW.{geom}), ST_MinY(NEW.{geom}), ST_MaxY(NEW.{geom})"
+        );
+        let triggers = [
+            format!(
+                "CREATE TRIGGER {insert} AFTER INSERT ON {table}
+                WHEN (NEW.{geom} NOT NULL AND NOT ST_IsEmpty(NEW.{geom}))
+                BEGIN INSERT OR REPLACE INTO {tree} VALUES ({envelope}); END"
+            ),
+            format!(
+                "CREATE TRIGGER {update1} AFTER UPDATE OF {geom} ON {table}
+                WHEN OLD.\"fid\" = NEW.\"fid\" AND (NEW.{geom} NOTNULL AND NOT ST_IsEmpty(NEW.{geom}))
+                BEGIN INSERT OR REPLACE INTO {tree} VALUES ({envelope}); END"
+            ),
+            format!(
+                "CREATE TRIGGER {update2} AFTER UPDATE OF {geom} ON {table}
+                WHEN OLD.\"fid\" = NEW.\"fid\" AND (NEW.{geom} ISNULL OR ST_IsEmpty(NEW.{geom}))
+                BEGIN DELETE FROM {tree} WHERE id = OLD.\"fid\"; END"
+            ),
+            format!(
+                "CREATE TRIGGER {update3} AFTER UPDATE ON {table}
+                WHEN OLD.\"fid\" != NEW.\"fid\" AND (NEW.{geom} NOTNULL AND NOT ST_IsEmpty(NEW.{geom}))
+                BEGIN DELETE FROM {tree} WHERE id = OLD.\"fid\";
+                INSERT OR REPLACE INTO {tree} VALUES ({envelope}); END"
+            ),
+            format!(
+                "CREATE TRIGGER {update4} AFTER UPDATE ON {table}
+                WHEN OLD.\"fid\" != NEW.\"fid\" AND (NEW.{geom} ISNULL OR ST_IsEmpty(NEW.{geom}))
+                BEGIN DELETE FROM {tree} WHERE id IN (OLD.\"fid\", NEW.\"fid\"); END"
+            ),
+            format!(
+                "CREATE TRIGGER {delete} AFTER DELETE ON {table}
+                WHEN OLD.{geom} NOT NULL
+                BEGIN DELETE FROM {tree} WHERE id = OLD.\"fid\"; END"
+            ),
+        ];
+        for trigger in triggers {
+            conn.execute(&trigger, [])?;
+        }
+
+        conn.execute(
+            "INSERT INTO gpkg_extensions (table_name, column_name, extension_name, definition, scope)
+            VALUES (?1, ?2, 'gpkg_rtree_index', 'http://www.geopackage.org/spec120/#extension_rtree', 'write-only')",
+            params![self.table, self.geometry_col],
+        )?;
+
+        return Ok(());
+    }
+}
+
+fn create_metadata_tables(conn: &Connection) -> rusqlite::Result<()> {
+    // Required GeoPackage tables, with the undefined & WGS84 reference systems
+    conn.pragma_update(None, "application_id", GPKG_APPLICATION_ID)?;
+    conn.pragma_update(None, "user_version", GPKG_USER_VERSION)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+            srs_name TEXT NOT NULL,
+            srs_id INTEGER NOT NULL PRIMARY KEY,
+            organization TEXT NOT NULL,
+            organization_coordsys_id INTEGER NOT NULL,
+            definition TEXT NOT NULL,
+            description TEXT
+        );
+        INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+            ('Undefined Cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined Cartesian coordinate reference system'),
+            ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system');
+        CREATE TABLE IF NOT EXISTS gpkg_contents (
+            table_name TEXT NOT NULL PRIMARY KEY,
+            data_type TEXT NOT NULL,
+            identifier TEXT UNIQUE,
+            description TEXT DEFAULT '',
+            last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+            min_x DOUBLE,
+            min_y DOUBLE,
+            max_x DOUBLE,
+            max_y DOUBLE,
+            srs_id INTEGER,
+            CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+        );
+        CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+            table_name TEXT NOT NULL,
+            column_name TEXT NOT NULL,
+            geometry_type_name TEXT NOT NULL,
+            srs_id INTEGER NOT NULL,
+            z TINYINT NOT NULL,
+            m TINYINT NOT NULL,
+            CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+            CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+            CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+            CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+        );
+        CREATE TABLE IF NOT EXISTS gpkg_extensions (
+            table_name TEXT,
+            column_name TEXT,
+            extension_name TEXT NOT NULL,
+            definition TEXT NOT NULL,
+            scope TEXT NOT NULL,
+            CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name)
+        );",
+    )?;
+
+    let wgs84 = crs_definitions::EPSG_4326;
+    conn.execute(
+        "INSERT OR IGNORE INTO gpkg_spatial_ref_sys
+        (srs_name, srs_id, organization, organization_coordsys_id, definition)
+        VALUES (?1, 4326, 'EPSG', 4326, ?2)",
+        params![srs_name(&wgs84), wgs84.wkt],
+    )?;
+
+    return Ok(());
+}
+
+fn srs_name(crs: &crs_definitions::Def) -> String {
+    // First quoted name in the WKT, e.g. PROJCS["OSGB 1936 / British National Grid"...
+    return crs
+        .wkt
+        .split('"')
+        .nth(1)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("EPSG:{}", crs.code));
+}
+
+fn writable_geometry(geometry: &Geometry) -> Geometry {
+    // Only the simple feature types have a WKB encoding
+    match geometry {
+        Geometry::Line(line) => return Geometry::LineString(line.into()),
+        Geometry::Rect(rect) => return Geometry::Polygon(rect.to_polygon()),
+        Geometry::Triangle(triangle) => return Geometry::Polygon(triangle.to_polygon()),
+        _ => return geometry.clone(),
+    }
+}
+
+fn geometry_type_name(geometries: &[Option<Geometry>]) -> &'static str {
+    // Shared type of every feature, or the generic GEOMETRY for mixed layers
+    let mut names = geometries.iter().flatten().map(|geometry| match geometry {
+        Geometry::Point(_) => "POINT",
+        Geometry::LineString(_) => "LINESTRING",
+        Geometry::Polygon(_) => "POLYGON",
+        Geometry::MultiPoint(_) => "MULTIPOINT",
+        Geometry::MultiLineString(_) => "MULTILINESTRING",
+        Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+        Geometry::GeometryCollection(_) => "GEOMCOLLECTION",
+        _ => "GEOMETRY",
+    });
+
+    let first = match names.next() {
+        Some(first) => first,
+        None => return "GEOMETRY",
+    };
+    match names.all(|name| name == first) {
+        true => return first,
+        false => return "GEOMETRY",
+    }
+}
+
+fn gpkg_wkb(geometry: &Geometry, rect: Option<Rect>, srs_id: u16) -> Vec<u8> {
+    // GeoPackage WKB, with an xy envelope for everything but points
+    let envelope = match (geometry, rect) {
+        (Geometry::Point(_), _) | (_, None) => vec![],
+        (_, Some(rect)) => vec![rect.min().x, rect.max().x, rect.min().y, rect.max().y],
+    };
+    return geometry
+        .to_gpkg_wkb(CoordDimensions::xy(), Some(srs_id as i32), envelope)
+        .expect("failed to encode geometry");
+}
+
+fn extend_rect(rect: Rect, other: Rect) -> Rect {
+    return Rect::new(
+        geo::coord! { x: rect.min().x.min(other.min().x), y: rect.min().y.min(other.min().y) },
+        geo::coord! { x: rect.max().x.max(other.max().x), y: rect.max().y.max(other.max().y) },
+    );
+}
+
+#[test]
+fn test_write_round_trip() {
+    use crate::geodb::{GeoDB, GeoSource};
+    use tempfile::TempDir;
+
+    let layer = GeoDB::example_polygons_local_db().extract().unwrap();
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding
+        .path()
+        .join("out.gpkg")
+        .to_str()
+        .expect("failed to convert path")
+        .to_string();
+
+    let writer = GeoWriter {
+        path: path.clone(),
+        table: "bathing_waters".to_string(),
+        crs: crs_definitions::EPSG_27700,
+        geometry_col: "geom".to_string(),
+        uuid_col: None,
+        spatial_index: true,
+    };
+    assert_eq!(writer.write(&layer.data), Ok(89));
+
+    // Read back through the gpkg metadata : no table, id or CRS hints
+    let reader = GeoDB {
+        source: GeoSource::Gpkg(path.clone()),
+        db: "out.gpkg".to_string(),
+        table: None,
+        crs: None,
+        uuid_col: Some("bw_url".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let written = reader.extract().unwrap();
+    assert_eq!(written.crs, Some(crs_definitions::EPSG_27700));
+    assert!(written.issues.is_empty());
+    assert_eq!(written.data.len(), 89);
+    for (row, original) in written.data.iter().zip(&layer.data) {
+        assert_eq!(row.uuid, original.uuid);
+        assert_eq!(row.geometry, original.geometry);
+        assert_eq!(row.property("year"), original.property("year"));
+        assert_eq!(
+            row.property("description"),
+            original.property("description")
+        );
+        assert_eq!(row.property("class_id"), original.property("class_id"));
+    }
+
+    let layers = reader.layers();
+    assert_eq!(layers[0].geometry_type.as_deref(), Some("MULTIPOLYGON"));
+    assert_eq!(layers[0].srs_id, Some(27700));
+    assert!(layers[0].bbox.is_some());
+    let year = layers[0]
+        .columns
+        .iter()
+        .find(|column| column.name == "year")
+        .unwrap();
+    assert_eq!(year.data_type, "INTEGER");
+
+    // GeoPackage flagged file, polygons carry an xy envelope, RTree holds every feature
+    let conn = Connection::open(&path).expect("failed to open written gpkg");
+    let application_id: i32 = conn
+        .query_row("PRAGMA application_id", [], |row| row.get(0))
+        .expect("failed to read application id");
+    assert_eq!(application_id, GPKG_APPLICATION_ID);
+
+    let wkb: Vec<u8> = conn
+        .query_row("SELECT geom FROM bathing_waters LIMIT 1", [], |row| {
+            row.get(0)
+        })
+        .expect("failed to read geometry");
+    assert_eq!(&wkb[0..2], b"GP");
+    assert_eq!((wkb[3] & 0b0000_1110) >> 1, 1);
+
+    let indexed: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM rtree_bathing_waters_geom",
+            [],
+            |row| row.get(0),
+        )
+        .expect("failed to count rtree");
+    assert_eq!(indexed, 89);
+}
+
+#[test]
+fn test_write_layers() {
+    use crate::geodb::{GeoDB, GeoSource};
+    use geo::{GeometryCollection, line_string, point};
+    use tempfile::TempDir;
+
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding
+        .path()
+        .join("out.gpkg")
+        .to_str()
+        .expect("failed to convert path")
+        .to_string();
+
+    let mut station = GeoData::from_geometry("station", point!(x: -3.2, y: 55.95));
+    station.properties.insert(
+        "sampled".to_string(),
+        PropertyValue::Date("2024-05-01".to_string()),
+    );
+    let river = GeoData::from_geometry(
+        "river",
+        line_string![(x: -3.2, y: 55.9), (x: -3.3, y: 56.0)],
+    );
+
+    // Mixed geometry layer in lon / lat, without a spatial index
+    let writer = GeoWriter {
+        path: path.clone(),
+        table: "sites".to_string(),
+        crs: crs_definitions::EPSG_4326,
+        geometry_col: "geom".to_string(),
+        uuid_col: Some("name".to_string()),
+        spatial_index: false,
+    };
+    assert_eq!(writer.write(&[station, river]), Ok(2));
+
+    // Second layer in the same gpkg
+    let points = GeoWriter {
+        table: "stations".to_string(),
+        ..writer
+    };
+    let stations = vec![GeoData::from_geometry("station", point!(x: -3.2, y: 55.95))];
+    assert_eq!(points.write(&stations), Ok(1));
+
+    // Collections use the GeoPackage type name
+    let collections = GeoWriter {
+        table: "outfalls".to_string(),
+        ..points
+    };
+    let outfall = GeoData::from_geometry(
+        "outfall",
+        Geometry::GeometryCollection(GeometryCollection::new_from(vec![
+            point!(x: -3.1, y: 55.9).into(),
+        ])),
+    );
+    assert_eq!(collections.write(&[outfall]), Ok(1));
+
+    let reader = GeoDB {
+        source: GeoSource::Gpkg(path),
+        db: "out.gpkg".to_string(),
+        table: Some("sites".to_string()),
+        crs: None,
+        uuid_col: Some("name".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let layers = reader.layers();
+    assert_eq!(layers.len(), 3);
+    assert_eq!(layers[0].geometry_type.as_deref(), Some("GEOMCOLLECTION"));
+    assert_eq!(layers[1].geometry_type.as_deref(), Some("GEOMETRY"));
+    assert_eq!(layers[2].geometry_type.as_deref(), Some("POINT"));
+
+    let written = reader.extract().unwrap();
+    assert_eq!(written.crs, Some(crs_definitions::EPSG_4326));
+    assert_eq!(written.data[0].uuid, "station");
+    assert_eq!(
+        written.data[0].property("sampled"),
+        Some(&PropertyValue::Date("2024-05-01".to_string()))
+    );
+    assert!(matches!(
+        written.data[1].geometry,
+        Some(Geometry::LineString(_))
+    ));
+}
+
+#[test]
+fn test_write_names_and_types() {
+    use crate::geodb::{GeoDB, GeoSource};
+    use geo::point;
+    use tempfile::TempDir;
+
+    let binding = TempDir::new().expect("failed to create test dir");
+    let path = binding
+        .path()
+        .join("out.gpkg")
+        .to_str()
+        .expect("failed to convert path")
+        .to_string();
+
+    // Property keys come straight from the data, quotes & all
+    let injection = "x\" TEXT); DROP TABLE gpkg_contents; --";
+    let mut gauge = GeoData::from_geometry("gauge", point!(x: -3.2, y: 55.95));
+    gauge
+        .properties
+        .insert("co\"de".to_string(), PropertyValue::Text("A1".to_string()));
+    gauge
+        .properties
+        .insert("le\"vel".to_string(), PropertyValue::Integer(1));
+    gauge
+        .properties
+        .insert(injection.to_string(), PropertyValue::Integer(0));
+    let mut weir = GeoData::from_geometry("weir", point!(x: -3.1, y: 55.9));
+    weir.properties
+        .insert("co\"de".to_string(), PropertyValue::Integer(7));
+    weir.properties
+        .insert("le\"vel".to_string(), PropertyValue::Real(2.5));
+
+    let writer = GeoWriter {
+        path: path.clone(),
+        table: "we\"ird".to_string(),
+        crs: crs_definitions::EPSG_4326,
+        geometry_col: "ge\"om".to_string(),
+        uuid_col: Some("na\"me".to_string()),
+        spatial_index: true,
+    };
+    assert_eq!(writer.write(&[gauge, weir]), Ok(2));
+
+    let reader = GeoDB {
+        source: GeoSource::Gpkg(path),
+        db: "out.gpkg".to_string(),
+        table: None,
+        crs: None,
+        uuid_col: Some("na\"me".to_string()),
+        uuid_col_idx: 0,
+        geometry_col_idx: 0,
+    };
+    let layers = reader.layers();
+    assert_eq!(layers.len(), 1);
+    assert_eq!(layers[0].table, "we\"ird");
+    assert_eq!(layers[0].geometry_column.as_deref(), Some("ge\"om"));
+
+    // Mixed text / integer widens to text, integer / real to real
+    let written = reader.extract().unwrap();
+    assert_eq!(written.data[0].uuid, "gauge");
+    assert!(written.data[0].point().is_some());
+    assert_eq!(
+        written.data[0].property("co\"de"),
+        Some(&PropertyValue::Text("A1".to_string()))
+    );
+    assert_eq!(
+        written.data[1].property("co\"de"),
+        Some(&PropertyValue::Text("7".to_string()))
+    );
+    assert_eq!(
+        written.data[0].property("le\"vel"),
+        Some(&PropertyValue::Real(1.0))
+    );
+    assert_eq!(
+        written.data[1].property("le\"vel"),
+        Some(&PropertyValue::Real(2.5))
+    );
+    assert_eq!(
+        written.data[0].property(injection),
+        Some(&PropertyValue::Integer(0))
+    );
+}